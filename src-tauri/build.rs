@@ -1,3 +1,45 @@
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
 fn main() {
+    // Build metadata for the support, see `logger::get_build_info`
+    let git_hash = std::env::var("TACHO_GIT_HASH").ok().or_else(git_hash);
+    println!(
+        "cargo:rustc-env=TACHO_GIT_HASH={}",
+        git_hash.as_deref().unwrap_or("unknown")
+    );
+
+    // SOURCE_DATE_EPOCH is respected for reproducible builds
+    let build_timestamp = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0)
+        });
+    println!("cargo:rustc-env=TACHO_BUILD_TIMESTAMP={}", build_timestamp);
+
+    println!("cargo:rerun-if-env-changed=TACHO_GIT_HASH");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs/heads");
+
     tauri_build::build()
 }
+
+/// Short hash of the current commit, marked "-dirty" with uncommitted changes.
+fn git_hash() -> Option<String> {
+    let output = Command::new("git")
+        .args(["describe", "--always", "--dirty", "--abbrev=12"])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let hash = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!hash.is_empty()).then_some(hash)
+}
@@ -3,10 +3,15 @@
 //! This module provides functionality for creating and managing MQTT connections.
 
 // ───── Std Lib ─────
+use std::collections::VecDeque; // Recent lost sessions of the takeover detection.
 use std::io::ErrorKind; // For categorizing I/O errors.
-use std::time::Duration; // For specifying time durations.
+use std::sync::atomic::{AtomicBool, Ordering}; // Connection status shared with the task pool.
+use std::sync::Arc; // Shared ownership of the connection status.
+use std::time::{Duration, Instant}; // For specifying time durations.
 
 // ───── MQTT Client Library (rumqttc) ─────
+use rumqttc::v5::mqttbytes::v5::DisconnectReasonCode; // Reason of the server disconnect.
+use rumqttc::v5::mqttbytes::QoS; // Quality of Service levels for MQTT.
 use rumqttc::v5::ConnectionError; // For handling MQTT connection errors.
 use rumqttc::v5::StateError::{self, AwaitPingResp, ServerDisconnect}; // Specific error for server disconnection.
 use rumqttc::v5::{AsyncClient, Event, Incoming, MqttOptions}; // Core MQTT async client and options.
@@ -18,13 +23,22 @@ use crate::smart_card::TASK_POOL; // Task pool for managing MQTT connections.
 use tauri::async_runtime::{self, JoinHandle}; // Async runtime and task join handles for Tauri apps.
 
 // ───── Serialization ─────
-use serde_json::Value; // For working with JSON data structures.
+use serde::Deserialize; // For parsing the management commands.
+use serde_json::{json, Value}; // For working with JSON data structures.
 
 // ───── Local Modules ─────
 use crate::config::get_from_cache; // Function to get data from cache for syncing server data.
+use crate::config::get_server_config; // Function to get the server settings from cache.
+use crate::config::get_server_credentials; // Function to get the broker credentials.
 use crate::config::split_host_to_parts; // Function to split the host into parts for MQTT connection.
 use crate::config::CacheSection; // Enum for cache sections for getting data from cache.
+use crate::global_app_handle::{emit_notification_event, NotificationPayload}; // Duplicate ident notification.
+use crate::logger::build_info; // Build metadata for the status report.
+use crate::mqtt::report_broker_error; // Broker errors to the frontend.
+use crate::mqtt::request_topic; // Request topic of the ident.
+use crate::mqtt::response_topic; // Response topic of the request.
 use crate::smart_card::ProcessingCard;
+use crate::smart_card::{connection_metrics, get_cards_snapshot, invalidate_reader_states}; // Handlers of the management commands.
 
 /// Timeout in seconds to wait before reconnecting to the server.
 ///
@@ -32,6 +46,103 @@ use crate::smart_card::ProcessingCard;
 /// to the MQTT server in case of connection loss.
 const SLEEP_DURATION_SECS: u64 = 10;
 
+/// A session lost sooner than this after the ConnAck counts for the takeover detection.
+const SHORT_SESSION: Duration = Duration::from_secs(30);
+
+/// Window the lost sessions of the takeover detection are counted in.
+const TAKEOVER_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// Detects the client ID contention: another machine provisioned with the same ident connects
+/// with the same client ID, the broker drops the older session and both reconnect in a loop.
+#[derive(Default)]
+struct TakeoverDetector {
+    connected_at: Option<Instant>, // Time of the ConnAck of the current session.
+    lost_sessions: VecDeque<Instant>, // Sessions taken over or lost shortly after the ConnAck.
+    reported: bool,                // The contention of the current burst is reported.
+}
+
+impl TakeoverDetector {
+    fn connected(&mut self) {
+        self.connected_at = Some(Instant::now());
+    }
+
+    /// Records the lost session. Returns true when the lost sessions within the window reach
+    /// the threshold, once per burst.
+    fn disconnected(&mut self, taken_over: bool, threshold: Option<u32>) -> bool {
+        let now = Instant::now();
+        let short_session = self
+            .connected_at
+            .take()
+            .is_some_and(|connected_at| now - connected_at < SHORT_SESSION);
+        if taken_over || short_session {
+            self.lost_sessions.push_back(now);
+        }
+
+        while self
+            .lost_sessions
+            .front()
+            .is_some_and(|lost| now - *lost > TAKEOVER_WINDOW)
+        {
+            self.lost_sessions.pop_front();
+        }
+        if self.lost_sessions.is_empty() {
+            self.reported = false;
+        }
+
+        match threshold {
+            Some(threshold) if !self.reported && self.lost_sessions.len() >= threshold as usize => {
+                self.reported = true;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Tells the user that another machine seems to use the same ident.
+fn report_duplicate_ident(ident: &str, lost_sessions: usize) {
+    log::warn!(
+        "{} | The connection was taken over {} times within {} min. Another machine seems to use the same ident.",
+        ident,
+        lost_sessions,
+        TAKEOVER_WINDOW.as_secs() / 60
+    );
+
+    let payload = NotificationPayload {
+        notification_type: "duplicate_ident".to_string(),
+        message: format!(
+            "Another machine seems to use the ident {}, the server connection keeps being taken over. Generate a new ident in the settings of one of the machines.",
+            ident
+        ),
+    };
+    emit_notification_event("global-notification", payload);
+}
+
+/// Management commands the server can send to the machine via the ident request topic,
+/// e.g. `{"command": "resync"}`. The answer is published to the response topic.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum AppCommand {
+    Resync,       // Re-read all readers, the inserted cards are processed again.
+    ReportStatus, // Report the build, the configured cards and their connections.
+}
+
+/// Executes the management command and returns the answer for the server.
+async fn handle_command(command: AppCommand) -> Value {
+    match command {
+        AppCommand::Resync => {
+            invalidate_reader_states();
+            json!({ "command": "resync", "result": "ok" })
+        }
+        AppCommand::ReportStatus => json!({
+            "command": "report_status",
+            "build": build_info(),
+            "cards": get_cards_snapshot().await,
+            "connections": connection_metrics().await,
+        }),
+    }
+}
+
 /// Ensures an MQTT connection for the specified client ID.
 #[tauri::command]
 pub async fn app_connection() {
@@ -75,18 +186,36 @@ pub async fn app_connection() {
     //////////////////////////////////////////////////
     let mut mqtt_options = MqttOptions::new(client_id.clone(), &host, port);
     // mqtt_options.set_credentials(flespi_token, "");
-    mqtt_options.set_keep_alive(Duration::from_secs(120));
-    log::debug!("mqtt_options: {:?}", mqtt_options);
+    mqtt_options.set_keep_alive(get_server_config().keep_alive_interval());
+    // Fail fast when the broker never accepts the connection (e.g. firewall drops)
+    mqtt_options.set_connection_timeout(get_server_config().connection_timeout_secs());
+    if let Some(credentials) = get_server_credentials() {
+        mqtt_options.set_credentials(credentials.username, credentials.password);
+    }
+    // The options are not logged as a whole, their Debug output contains the credentials
+    log::debug!(
+        "mqtt_options: host {}:{}, client_id {}, keep_alive {:?}",
+        host,
+        port,
+        client_id,
+        mqtt_options.keep_alive()
+    );
 
     // Create a new asynchronous MQTT client and its associated event loop
     // `mqtt_options` specifies the configuration for the MQTT connection
-    // `10` is the capacity of the internal channel used by the event loop for buffering operations
-    let (mqtt_client, mut eventloop) = AsyncClient::new(mqtt_options, 10);
+    // The capacity of the internal channel buffering the requests is configurable, see `channel_capacity`
+    let (mqtt_client, mut eventloop) =
+        AsyncClient::new(mqtt_options, get_server_config().channel_capacity());
     let mqtt_clinet_cloned = mqtt_client.clone();
+    let client_id_cloned = client_id.clone();
     let log_header: String = format!("{} |", client_id);
+    let online_state = Arc::new(AtomicBool::new(false)); // Connection status shared with the task pool
+    let online_state_cloned = Arc::clone(&online_state);
 
     // create async task for the mqtt client
     let handle: JoinHandle<()> = async_runtime::spawn(async move {
+        let mut takeover_detector = TakeoverDetector::default();
+
         loop {
             match eventloop.poll().await {
                 Ok(notification) => {
@@ -94,46 +223,104 @@ pub async fn app_connection() {
 
                     match notification {
                         Event::Incoming(Incoming::Publish(publish)) => {
-                            // Extracting the topic from the incoming data
-                            // let topic_str = match std::str::from_utf8(&publish.topic) {
-                            //     Ok(str) => str,
-                            //     Err(e) => {
-                            //         eprintln!("Error converting topic from bytes to string: {:?}", e);
-                            //         return;
-                            //     }
-                            // };
-
-                            // Convert &str to String for further use
-                            // let topic = topic_str.to_string();
-                            // The contents of response and request are the same.
-                            // Card number and parcel ID. So we just change the initial topic
-                            // let topic_ack = topic.replace("request", "response");
+                            // The response topic mirrors the request topic
+                            let topic_ack =
+                                response_topic(&String::from_utf8_lossy(&publish.topic));
 
                             // serializable data to interpret it as json
-                            match serde_json::from_slice::<Value>(&publish.payload) {
-                                Ok(json_payload) => {
-                                    log::debug!("Parsed JSON payload: {:?}", json_payload);
-                                    // The "hex" parameter contains the apdu instruction that needs to be transferred to the card
-                                }
-                                Err(e) => {
-                                    log::error!(
-                                        "{} parsing JSON payload issue: {:?}",
+                            let json_payload =
+                                match serde_json::from_slice::<Value>(&publish.payload) {
+                                    Ok(json_payload) => json_payload,
+                                    Err(e) => {
+                                        log::error!(
+                                            "{} parsing JSON payload issue: {:?}",
+                                            log_header,
+                                            e
+                                        );
+                                        continue;
+                                    }
+                                };
+                            log::debug!("Parsed JSON payload: {:?}", json_payload);
+
+                            let command = json_payload.get("command").cloned().unwrap_or_default();
+                            let command = match serde_json::from_value::<AppCommand>(command) {
+                                Ok(command) => command,
+                                Err(_) => {
+                                    log::warn!(
+                                        "{} Unknown command is ignored: {}",
                                         log_header,
-                                        e
+                                        json_payload
                                     );
+                                    continue;
                                 }
+                            };
+
+                            log::info!("{} Command received: {:?}", log_header, command);
+                            let answer = handle_command(command).await;
+
+                            if let Err(e) = mqtt_client
+                                .publish(topic_ack, QoS::AtLeastOnce, false, answer.to_string())
+                                .await
+                            {
+                                log::error!("{} Failed to publish the answer: {:?}", log_header, e);
                             }
                         }
                         Event::Incoming(Incoming::ConnAck(..)) => {
+                            online_state_cloned.store(true, Ordering::Relaxed);
+                            takeover_detector.connected();
                             log::info!(
                                 "{} Connection to the server has been successfully established.",
                                 log_header
-                            )
+                            );
+
+                            // Subscribe on every (re)connect to receive the management commands
+                            let topic = request_topic(&client_id_cloned);
+                            if let Err(e) =
+                                mqtt_client.subscribe(topic.clone(), QoS::AtLeastOnce).await
+                            {
+                                log::error!(
+                                    "{} Failed to subscribe to {}: {:?}",
+                                    log_header,
+                                    topic,
+                                    e
+                                );
+                            }
                         }
                         _ => {} // This handles any other events that you haven't explicitly matched above
                     }
                 }
                 Err(e) => {
+                    online_state_cloned.store(false, Ordering::Relaxed);
+
+                    // The same client ID connected elsewhere, see `TakeoverDetector`
+                    let taken_over = matches!(
+                        e,
+                        ConnectionError::MqttState(ServerDisconnect {
+                            reason_code: DisconnectReasonCode::SessionTakenOver,
+                            ..
+                        })
+                    );
+                    if takeover_detector
+                        .disconnected(taken_over, get_server_config().takeover_threshold())
+                    {
+                        report_duplicate_ident(
+                            &client_id_cloned,
+                            takeover_detector.lost_sessions.len(),
+                        );
+                    }
+
+                    // The errors are expected during a planned broker downtime, they are only traced
+                    if let Some(retry) = get_server_config().maintenance_retry_interval() {
+                        log::debug!(
+                            "{} Connection error in the maintenance window: {}",
+                            log_header,
+                            e
+                        );
+                        tokio::time::sleep(retry).await;
+                        continue;
+                    }
+
+                    report_broker_error(&client_id_cloned, &e);
                     match e {
                         ConnectionError::Io(ref io_err) => match io_err.kind() {
                             ErrorKind::ConnectionAborted => log::warn!("{} Can't establish a connection to a remote server.", log_header),
@@ -141,6 +328,7 @@ pub async fn app_connection() {
                             ErrorKind::TimedOut => log::warn!("{} Connection timeout. The server may be down or the network is unstable.", log_header),
                             _ => log::error!("{} An IO error occurred.", log_header),
                         },
+                        ConnectionError::Timeout(_) => log::warn!("{} Connection timeout. The server did not accept the connection in time.", log_header),
                         ConnectionError::MqttState(ServerDisconnect { .. }) => log::warn!("{} The connection was terminated on the server side. Most likely the user has turned off the channel/device.", log_header),
                         ConnectionError::MqttState(AwaitPingResp { .. }) => {
                             log::warn!("{} Awaiting PING response from the server. The connection might be unstable.", log_header);
@@ -165,6 +353,13 @@ pub async fn app_connection() {
         atr: None,
         mqtt_client: mqtt_clinet_cloned,
         task_handle: handle,
+        managed_card: None,
+        online: online_state,
+        metrics: Default::default(),
+        auth_process: Default::default(),
+        stop: Default::default(),
+        paused: Default::default(),
+        release_exchange: Default::default(),
     });
 
     for (i, card) in task_pool.iter().enumerate() {
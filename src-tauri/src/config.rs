@@ -6,20 +6,92 @@ use std::fs::File;
 use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 // ───── External Crates ─────
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Weekday};
 use lazy_static::lazy_static;
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use serde_yaml;
+use tauri::async_runtime;
 use tauri::Emitter;
 use tauri::Manager;
 
 // ───── Local Modules ─────
+use crate::app_connect::app_connection;
+use crate::credentials::{delete_from_keyring, load_from_keyring, store_in_keyring, Credentials};
 use crate::global_app_handle::emit_card_config_event;
+use crate::global_app_handle::emit_notification_event;
 use crate::global_app_handle::get_app_handle;
-use crate::mqtt::remove_connections;
-// use crate::smart_card::manual_sync_cards;
+use crate::global_app_handle::NotificationPayload;
+use crate::mqtt::{remove_card_connections, remove_connections, remove_connections_all};
+use crate::smart_card::invalidate_reader_states;
+
+/// Error of a command for the frontend: a stable code the UI can show a localized message for,
+/// and the details for the log and the support.
+#[derive(Serialize, Debug, Clone)]
+pub struct ErrorInfo {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+/// Stable error codes of `ErrorInfo`.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    ConfigPath, // The location of the configuration file can not be resolved.
+    Io,         // The configuration file can not be read or written.
+    Parse,      // The configuration file is not valid YAML of the expected format.
+    Validation, // The given values are rejected, e.g. an ICCID assigned to another card.
+    Managed,    // The configuration is managed centrally and is not changed by the app.
+}
+
+/// Refusal to write the managed configuration, see `config_managed_reason`.
+#[derive(Debug)]
+pub struct ConfigManaged(String);
+
+impl std::fmt::Display for ConfigManaged {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Configuration is managed: {}", self.0)
+    }
+}
+
+impl std::error::Error for ConfigManaged {}
+
+impl ErrorInfo {
+    /// Error of the config path resolution, see `get_config_path`.
+    fn config_path(e: io::Error) -> Self {
+        log::error!("Failed to get config path: {}", e);
+        Self {
+            code: ErrorCode::ConfigPath,
+            message: format!("Failed to get config path: {}", e),
+        }
+    }
+
+    /// Classifies an error of the configuration handling. The rejected values are reported
+    /// as `io::ErrorKind::InvalidInput` or `AlreadyExists`.
+    fn config(context: &str, e: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        let code = if e.downcast_ref::<serde_yaml::Error>().is_some() {
+            ErrorCode::Parse
+        } else if e.downcast_ref::<ConfigManaged>().is_some() {
+            ErrorCode::Managed
+        } else {
+            match e.downcast_ref::<io::Error>().map(io::Error::kind) {
+                Some(io::ErrorKind::InvalidInput | io::ErrorKind::AlreadyExists) => {
+                    ErrorCode::Validation
+                }
+                _ => ErrorCode::Io,
+            }
+        };
+
+        log::error!("{}: {}", context, e);
+        Self {
+            code,
+            message: format!("{}: {}", context, e),
+        }
+    }
+}
 
 /// Represents the configuration settings for the application.
 #[derive(Serialize, Deserialize, Debug)]
@@ -31,12 +103,220 @@ pub struct ConfigurationFile {
     ident: Option<String>,                // Optional ident for the application.
     server: Option<ServerConfig>,         // Optional server configuration settings.
     cards: HashMap<String, CardConfig>,   // Hashmap of the cards with the CardConfig structure
+    #[serde(default)]
+    smart_card: Option<SmartCardConfig>, // Optional tuning of the smart card processing.
+    #[serde(default)]
+    allowed_cards: Option<Vec<String>>, // Card numbers allowed to connect. Absent or empty allows all.
+    #[serde(default)]
+    min_version: Option<String>, // Older versions must be updated, the update is reported as mandatory.
+    #[serde(default)]
+    reserved_idents: Option<Vec<String>>, // Idents taken by other machines of the fleet, see `set_ident`.
+    #[serde(default)]
+    ident_duplicates: IdentDuplicatePolicy, // What `set_ident` does with a reserved ident. Defaults to reject.
+    #[serde(default)]
+    log_levels: Option<HashMap<String, String>>, // Log level per module path, e.g. rumqttc: warn. Read at startup.
+    #[serde(default)]
+    config_check_interval_secs: Option<u64>, // Compare the cache with the file this often, see `config_integrity_monitor`. Absent or 0 disables it.
+    #[serde(default)]
+    read_only: bool, // Provisioned centrally: the app doesn't write the file and the UI disables editing.
+    #[serde(default)]
+    schema_version: u32, // Version of the configuration format, see `migrate_config`.
 }
 
+// Handling of a reserved ident in `set_ident`, part of ConfigurationFile.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentDuplicatePolicy {
+    #[default]
+    Reject, // The reserved ident is refused.
+    Warn, // The reserved ident is accepted with a warning in the log.
+}
+
+/// Longest ident accepted by `set_ident`, it is a part of the MQTT client IDs and topics.
+const MAX_IDENT_LEN: usize = 64;
+
 // Server Configuration structure, part of ConfigurationFile that contains data about the server.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+// Debug is implemented by hand to keep the credentials out of the log.
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct ServerConfig {
     pub host: String,
+    #[serde(default)]
+    pub connection_timeout: Option<u64>, // Timeout of the broker connect in seconds. Defaults to 15.
+    #[serde(default)]
+    pub username: Option<String>, // Broker username, only if the keyring is not available.
+    #[serde(default)]
+    pub password: Option<String>, // Broker password, only if the keyring is not available.
+    #[serde(default)]
+    pub credentials_in_keyring: bool, // Marker: the credentials are stored in the OS keyring.
+    #[serde(default)]
+    pub topic_prefix: Option<String>, // Prefix of the card topics. Defaults to "tacho-bridge".
+    #[serde(default)]
+    pub qos: Option<u8>, // QoS of the card subscriptions and answers. Defaults to 1.
+    #[serde(default)]
+    pub keep_alive: Option<u64>, // MQTT keep-alive of the card and app connections in seconds. Defaults to 120.
+    #[serde(default)]
+    pub maintenance_windows: Option<Vec<MaintenanceWindow>>, // Planned broker downtime, local time.
+    #[serde(default)]
+    pub maintenance_retry_secs: Option<u64>, // Reconnect interval inside a maintenance window. Defaults to 300.
+    #[serde(default)]
+    pub channel_capacity: Option<usize>, // Requests buffered by the MQTT client of a connection. Defaults to 10.
+    #[serde(default)]
+    pub takeover_threshold: Option<u32>, // Lost sessions of the app connection that report a duplicate ident. Defaults to 3, 0 disables.
+}
+
+impl std::fmt::Debug for ServerConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServerConfig")
+            .field("host", &self.host)
+            .field("connection_timeout", &self.connection_timeout)
+            .field("username", &self.username.as_ref().map(|_| "<redacted>"))
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("credentials_in_keyring", &self.credentials_in_keyring)
+            .field("topic_prefix", &self.topic_prefix)
+            .field("qos", &self.qos)
+            .field("keep_alive", &self.keep_alive)
+            .field("maintenance_windows", &self.maintenance_windows)
+            .field("maintenance_retry_secs", &self.maintenance_retry_secs)
+            .field("channel_capacity", &self.channel_capacity)
+            .field("takeover_threshold", &self.takeover_threshold)
+            .finish()
+    }
+}
+
+// Planned downtime of the broker, part of ServerConfig.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MaintenanceWindow {
+    pub start: String, // "2026-11-02 22:00" for a single window, "22:00" for a recurring one.
+    pub end: String,   // Same format as the start. A recurring window may end after midnight.
+    #[serde(default)]
+    pub days: Option<Vec<String>>, // Days a recurring window starts on, e.g. ["sat", "sun"]. Absent for every day.
+}
+
+impl MaintenanceWindow {
+    /// Returns whether the local time is inside the window. An unparsable window never matches.
+    fn contains(&self, now: NaiveDateTime) -> bool {
+        const DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+        const TIME_FORMAT: &str = "%H:%M";
+
+        if let (Ok(start), Ok(end)) = (
+            NaiveDateTime::parse_from_str(&self.start, DATE_TIME_FORMAT),
+            NaiveDateTime::parse_from_str(&self.end, DATE_TIME_FORMAT),
+        ) {
+            return start <= now && now < end;
+        }
+
+        let (Ok(start), Ok(end)) = (
+            NaiveTime::parse_from_str(&self.start, TIME_FORMAT),
+            NaiveTime::parse_from_str(&self.end, TIME_FORMAT),
+        ) else {
+            return false;
+        };
+
+        let starts_on = |day: Weekday| match &self.days {
+            Some(days) => days
+                .iter()
+                .any(|listed| listed.parse::<Weekday>().is_ok_and(|listed| listed == day)),
+            None => true,
+        };
+
+        let time = now.time();
+        if start <= end {
+            start <= time && time < end && starts_on(now.weekday())
+        } else {
+            // The window crosses midnight, after midnight it belongs to the previous day
+            (time >= start && starts_on(now.weekday()))
+                || (time < end && starts_on(now.weekday().pred()))
+        }
+    }
+}
+
+/// Default timeout of the broker connect, in seconds.
+const DEFAULT_CONNECTION_TIMEOUT_SECS: u64 = 15;
+/// Default keep-alive of the broker connections, in seconds.
+const DEFAULT_KEEP_ALIVE_SECS: u64 = 120;
+/// Default reconnect interval inside a maintenance window, in seconds.
+const DEFAULT_MAINTENANCE_RETRY_SECS: u64 = 300;
+/// Lowest keep-alive accepted, a shorter one only floods the broker with pings.
+const MIN_KEEP_ALIVE_SECS: u64 = 5;
+/// Highest keep-alive accepted, MQTT carries it in two bytes.
+const MAX_KEEP_ALIVE_SECS: u64 = u16::MAX as u64;
+/// Period the disabled config integrity check looks up its setting again.
+const CONFIG_CHECK_IDLE_INTERVAL: Duration = Duration::from_secs(60);
+/// Pause before a detected drift is confirmed, a save may be between the file and the cache.
+const CONFIG_CHECK_RECHECK_DELAY: Duration = Duration::from_secs(1);
+/// Default number of lost sessions reporting a duplicate ident.
+const DEFAULT_TAKEOVER_THRESHOLD: u32 = 3;
+/// Default capacity of the MQTT client request channel.
+const DEFAULT_CHANNEL_CAPACITY: usize = 10;
+/// Highest capacity of the MQTT client request channel accepted from the frontend.
+const MAX_CHANNEL_CAPACITY: usize = 1000;
+/// Default prefix of the topics of the tacho-bridge channel.
+///
+/// The server publishes APDU requests to `{prefix}/{card_number}/request`
+/// and expects the answers in `{prefix}/{card_number}/response`.
+const DEFAULT_TOPIC_PREFIX: &str = "tacho-bridge";
+
+impl ServerConfig {
+    /// Returns the prefix of the topics, the cards may override it, see `get_card_mqtt_settings`.
+    pub fn topic_prefix(&self) -> String {
+        self.topic_prefix
+            .clone()
+            .unwrap_or_else(|| DEFAULT_TOPIC_PREFIX.to_string())
+    }
+
+    /// Returns the timeout of the broker connect in seconds.
+    pub fn connection_timeout_secs(&self) -> u64 {
+        self.connection_timeout
+            .unwrap_or(DEFAULT_CONNECTION_TIMEOUT_SECS)
+    }
+
+    /// Returns the MQTT keep-alive of the broker connections, the cards and the app connection.
+    ///
+    /// This is not a TCP keepalive: rumqttc doesn't expose the socket, so SO_KEEPALIVE is not
+    /// set. A ping is sent after this interval without traffic and the connection is dropped
+    /// when the ping is still unanswered at the next interval, so a dead link behind a carrier
+    /// NAT is noticed within about twice the interval. Mobile deployments lower it below the
+    /// NAT timeout of the carrier, which also keeps the NAT mapping alive.
+    pub fn keep_alive_interval(&self) -> Duration {
+        Duration::from_secs(
+            self.keep_alive
+                .unwrap_or(DEFAULT_KEEP_ALIVE_SECS)
+                .max(MIN_KEEP_ALIVE_SECS),
+        )
+    }
+
+    /// Returns the number of lost sessions of the app connection within the window after which
+    /// another machine with the same ident is reported, None if the detection is disabled.
+    pub fn takeover_threshold(&self) -> Option<u32> {
+        Some(
+            self.takeover_threshold
+                .unwrap_or(DEFAULT_TAKEOVER_THRESHOLD),
+        )
+        .filter(|threshold| *threshold > 0)
+    }
+
+    /// Returns the capacity of the request channel of the MQTT client, at least 1.
+    pub fn channel_capacity(&self) -> usize {
+        self.channel_capacity
+            .unwrap_or(DEFAULT_CHANNEL_CAPACITY)
+            .max(1)
+    }
+
+    /// Returns the reconnect interval if the broker is in a planned maintenance window now,
+    /// None outside the windows. Inside a window the connection errors are not reported.
+    pub fn maintenance_retry_interval(&self) -> Option<Duration> {
+        let now = chrono::Local::now().naive_local();
+        self.maintenance_windows
+            .as_ref()?
+            .iter()
+            .any(|window| window.contains(now))
+            .then(|| {
+                Duration::from_secs(
+                    self.maintenance_retry_secs
+                        .unwrap_or(DEFAULT_MAINTENANCE_RETRY_SECS),
+                )
+            })
+    }
 }
 
 // Dark Theme enum, part of AppearanceConfig that contains data about the theme.
@@ -52,34 +332,445 @@ pub struct CardConfig {
     pub iccid: String,        // ICCID
     pub expire: Option<u64>,  // Expire date
     pub name: Option<String>, // Custom card name (for ease of user identification)
+    #[serde(default)]
+    pub enabled: Option<bool>, // Broker connection of the card is enabled. Defaults to true.
+    #[serde(default)]
+    pub reader: Option<String>, // Preferred reader, the card is not connected from other readers.
+    #[serde(default)]
+    pub topic_prefix: Option<String>, // Topic prefix of the card instead of the server one, e.g. a separate channel.
+    #[serde(default)]
+    pub qos: Option<u8>, // QoS of the card instead of the server one.
+}
+
+impl CardConfig {
+    /// Returns whether the card may be connected to the broker.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+}
+
+// Smart card Configuration structure, part of ConfigurationFile that contains tuning of the card processing.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SmartCardConfig {
+    #[serde(default)]
+    pub max_concurrent_io: Option<usize>, // Limit of simultaneous card I/O operations. Defaults to the CPU count.
+    #[serde(default)]
+    pub debounce_ms: Option<u64>, // Time a reader state must be stable before it is processed, e.g. 300 for flaky readers. Defaults to 0, disabled.
+    #[serde(default)]
+    pub dry_run: bool, // Log incoming APDUs and answer with a stub instead of sending them to the card.
+    #[serde(default)]
+    pub dry_run_response: Option<String>, // Stub response in the dry-run mode. Defaults to "9000".
+    #[serde(default)]
+    pub maintenance: bool, // Maintenance mode: the cards are not touched until it is disabled.
+    #[serde(default)]
+    pub apdu_trace: bool, // Write the APDU requests and responses of every card to apdu.log.
+    #[serde(default)]
+    pub apdu_events: bool, // Emit every APDU of the server to the protocol console of the UI.
+    #[serde(default)]
+    pub redact_sensitive: bool, // Show only the command headers and the status words in the APDU events.
+    #[serde(default)]
+    pub power_saving: Option<bool>, // Re-list the readers only on PnP changes. Defaults to true except on Windows.
+    #[serde(default)]
+    pub expire_warning_days: Option<u64>, // Warn about the cards expiring within this number of days. 0 disables the warning.
+    #[serde(default)]
+    pub iccid_apdus: Option<Vec<String>>, // APDUs reading the ICCID, the last one returns it. For non-standard cards.
+    #[serde(default)]
+    pub breaker_failures: Option<u32>, // APDU failures in a row that pause the card. Defaults to 5, 0 disables.
+    #[serde(default)]
+    pub breaker_window_secs: Option<u64>, // Window the failures are counted in. Defaults to 60.
+    #[serde(default)]
+    pub breaker_cooldown_secs: Option<u64>, // Time the card is paused for. Defaults to 300.
+    #[serde(default)]
+    pub card_number_source: CardNumberSource, // Where the card number comes from. Defaults to iccid_map.
+    #[serde(default)]
+    pub insert_delay_ms: Option<u64>, // Pause after the card insertion before the first APDU. Defaults to 100.
+    #[serde(default)]
+    pub reader_insert_delay_ms: Option<HashMap<String, u64>>, // Insertion pause per reader, keyed by a part of the reader name.
+    #[serde(default)]
+    pub atr_prefixes: Option<Vec<String>>, // ATR prefixes (hex) of the tachograph cards. Absent or empty processes all cards.
+    #[serde(default)]
+    pub startup_scan: Option<bool>, // Process the already inserted cards at startup, without waiting for a change. Defaults to true.
+    #[serde(default)]
+    pub iccid_retries: Option<u32>, // Extra attempts to read the ICCID of an inserted card. Defaults to 2.
+    #[serde(default)]
+    pub iccid_retry_delay_ms: Option<u64>, // Pause between the ICCID attempts. Defaults to 500.
+    #[serde(default)]
+    pub atr_card_numbers: Option<HashMap<String, String>>, // Card number per ATR (hex), used when the ICCID can't be read.
+    #[serde(default)]
+    pub idle_timeout_secs: Option<u64>, // Disconnect the card after this time without requests. Absent or 0 disables it.
+    #[serde(default)]
+    pub watchdog_timeout_secs: Option<u64>, // Restart the reader monitor when it is stalled this long. Defaults to 60, 0 disables.
+    #[serde(default)]
+    pub event_history_size: Option<usize>, // Card state events kept for card_event_history. Defaults to 200.
+    #[serde(default)]
+    pub pcsc_scope: PcscScope, // PC/SC scope the readers are looked up in. Defaults to user.
+    #[serde(default)]
+    pub company_card_pool: Option<bool>, // Connect any company card by the number read from it, no card mapping needed.
+    #[serde(default)]
+    pub unregistered_cards: UnregisteredCardPolicy, // What is done with a card without a card number. Defaults to notify.
+    #[serde(default)]
+    pub shutdown_grace_ms: Option<u64>, // Time a removed card task gets to stop and release the card before it is aborted. Defaults to 2000.
+    #[serde(default)]
+    pub success_status_words: Option<Vec<String>>, // Status words (hex) the authentication progress counts as success, e.g. 9000 and 6283. Defaults to 9000.
+    #[serde(default)]
+    pub pin_tries_on_connect: bool, // Read the PIN try counter of the workshop cards on connect. Otherwise only on demand, see get_pin_tries.
+}
+
+// Source of the card number, part of SmartCardConfig.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CardNumberSource {
+    #[default]
+    IccidMap, // The ICCID is looked up in the configured cards.
+    ReadFromCard, // The card number is read from EF Identification of the card.
+    Both,         // The configured cards first, then the card itself.
+}
+
+// Handling of a card whose card number is unknown, part of SmartCardConfig.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UnregisteredCardPolicy {
+    #[default]
+    Notify, // The frontend is told, so the user can map the ICCID to a card number.
+    Ignore, // The card is only logged.
+}
+
+// PC/SC scope of the card contexts, part of SmartCardConfig.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PcscScope {
+    #[default]
+    User, // The readers visible to the current user.
+    System, // The readers of the system, needed on some Windows service and kiosk setups.
 }
+
+impl SmartCardConfig {
+    /// Returns the limit of simultaneous card I/O operations, at least 1.
+    pub fn max_concurrent_io(&self) -> usize {
+        self.max_concurrent_io
+            .unwrap_or_else(|| {
+                std::thread::available_parallelism()
+                    .map(|n| n.get())
+                    .unwrap_or(1)
+            })
+            .max(1)
+    }
+
+    /// Returns the stub response used in the dry-run mode.
+    pub fn dry_run_response(&self) -> String {
+        self.dry_run_response
+            .clone()
+            .unwrap_or_else(|| "9000".to_string())
+    }
+
+    /// Returns whether the reader monitor runs in the power saving mode.
+    ///
+    /// The mode saves work on every wake-up of the monitor, not wake-ups: the readers are not
+    /// re-listed and the task doesn't yield after the pass. The monitor still wakes up on every
+    /// reader status change and, with the watchdog on, at least every 10 s.
+    pub fn power_saving(&self) -> bool {
+        self.power_saving.unwrap_or(!cfg!(target_os = "windows"))
+    }
+
+    /// Returns whether the cards already inserted at startup are processed right away.
+    pub fn startup_scan(&self) -> bool {
+        self.startup_scan.unwrap_or(true)
+    }
+
+    /// Returns the number of days before the expiry when the card is reported.
+    pub fn expire_warning_days(&self) -> u64 {
+        self.expire_warning_days
+            .unwrap_or(DEFAULT_EXPIRE_WARNING_DAYS)
+    }
+
+    /// Returns the APDU sequence reading the ICCID: the selection steps and the read as the last one.
+    /// None when the ICCID is read from EF ICC (0002) of a standard card.
+    pub fn iccid_apdus(&self) -> Option<Vec<String>> {
+        self.iccid_apdus.clone().filter(|apdus| !apdus.is_empty())
+    }
+
+    /// Drops the ICCID APDU override if any step is not a valid APDU in hex.
+    fn validated(mut self) -> Self {
+        if let Some(apdus) = &self.iccid_apdus {
+            let invalid = apdus
+                .iter()
+                .find(|apdu| hex::decode(apdu).map_or(true, |bytes| bytes.len() < 4));
+
+            if let Some(invalid) = invalid {
+                log::error!(
+                    "Invalid ICCID APDU '{}' in the configuration. The default sequence is used.",
+                    invalid
+                );
+                self.iccid_apdus = None;
+            }
+        }
+        self
+    }
+
+    /// Returns the number of APDU failures in a row that trip the circuit breaker, 0 disables it.
+    pub fn breaker_failures(&self) -> u32 {
+        self.breaker_failures.unwrap_or(DEFAULT_BREAKER_FAILURES)
+    }
+
+    /// Returns the window the APDU failures are counted in.
+    pub fn breaker_window(&self) -> Duration {
+        Duration::from_secs(
+            self.breaker_window_secs
+                .unwrap_or(DEFAULT_BREAKER_WINDOW_SECS),
+        )
+    }
+
+    /// Returns the time the card is not used after the circuit breaker is tripped.
+    pub fn breaker_cooldown(&self) -> Duration {
+        Duration::from_secs(
+            self.breaker_cooldown_secs
+                .unwrap_or(DEFAULT_BREAKER_COOLDOWN_SECS),
+        )
+    }
+
+    /// Returns whether the card with the given ATR (hex) is processed.
+    /// Without ATR prefixes in the configuration every card is processed.
+    pub fn is_atr_allowed(&self, atr: &str) -> bool {
+        match &self.atr_prefixes {
+            Some(prefixes) if !prefixes.is_empty() => prefixes.iter().any(|prefix| {
+                let prefix = prefix.trim();
+                atr.len() >= prefix.len() && atr[..prefix.len()].eq_ignore_ascii_case(prefix)
+            }),
+            _ => true,
+        }
+    }
+
+    /// Returns whether the status word ending the response (hex) counts as success for the
+    /// authentication progress. The response itself is forwarded to the server in any case.
+    pub fn is_success_status(&self, response: &str) -> bool {
+        let Some(status_word) = response
+            .len()
+            .checked_sub(4)
+            .and_then(|start| response.get(start..))
+        else {
+            return false;
+        };
+
+        match &self.success_status_words {
+            Some(status_words) if !status_words.is_empty() => status_words
+                .iter()
+                .any(|word| word.trim().eq_ignore_ascii_case(status_word)),
+            _ => status_word.eq_ignore_ascii_case(DEFAULT_SUCCESS_STATUS_WORD),
+        }
+    }
+
+    /// Returns the pause after the card insertion, so the card has time to power up.
+    /// A reader override matching a part of the reader name wins over the common value.
+    pub fn insert_delay(&self, reader_name: &str) -> Duration {
+        let reader_delay = self.reader_insert_delay_ms.as_ref().and_then(|delays| {
+            delays
+                .iter()
+                .find(|(pattern, _)| reader_name.contains(pattern.as_str()))
+                .map(|(_, delay)| *delay)
+        });
+
+        Duration::from_millis(
+            reader_delay
+                .or(self.insert_delay_ms)
+                .unwrap_or(DEFAULT_INSERT_DELAY_MS),
+        )
+    }
+
+    /// Returns the number of extra attempts to read the ICCID after the first one fails.
+    pub fn iccid_retries(&self) -> u32 {
+        self.iccid_retries.unwrap_or(DEFAULT_ICCID_RETRIES)
+    }
+
+    /// Returns the pause between the ICCID attempts.
+    pub fn iccid_retry_delay(&self) -> Duration {
+        Duration::from_millis(
+            self.iccid_retry_delay_ms
+                .unwrap_or(DEFAULT_ICCID_RETRY_DELAY_MS),
+        )
+    }
+
+    /// Returns the card number mapped to the ATR, the fallback when the ICCID can't be read.
+    pub fn atr_card_number(&self, atr: &str) -> Option<String> {
+        self.atr_card_numbers.as_ref().and_then(|numbers| {
+            numbers
+                .iter()
+                .find(|(mapped_atr, _)| mapped_atr.eq_ignore_ascii_case(atr))
+                .map(|(_, card_number)| card_number.clone())
+        })
+    }
+
+    /// Returns the time without requests after which the card is disconnected, None if disabled.
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout_secs
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// Returns the time the reader monitor may be silent before it is restarted, None if disabled.
+    pub fn watchdog_timeout(&self) -> Option<Duration> {
+        Some(
+            self.watchdog_timeout_secs
+                .unwrap_or(DEFAULT_WATCHDOG_TIMEOUT_SECS),
+        )
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+    }
+
+    /// Returns whether any inserted company card is connected by the card number read from it.
+    /// Workshops rotating many company cards don't have to register each ICCID, other card
+    /// types are not connected in this mode.
+    pub fn company_card_pool(&self) -> bool {
+        self.company_card_pool.unwrap_or(false)
+    }
+
+    /// Returns the number of card state events kept in the history.
+    pub fn event_history_size(&self) -> usize {
+        self.event_history_size
+            .unwrap_or(DEFAULT_EVENT_HISTORY_SIZE)
+    }
+
+    /// Returns the time a removed card task gets to stop on its own before it is aborted.
+    pub fn shutdown_grace(&self) -> Duration {
+        Duration::from_millis(self.shutdown_grace_ms.unwrap_or(DEFAULT_SHUTDOWN_GRACE_MS))
+    }
+
+    /// Returns the interval a reader state must be stable before it is processed.
+    pub fn debounce_interval(&self) -> Duration {
+        Duration::from_millis(self.debounce_ms.unwrap_or(DEFAULT_DEBOUNCE_MS))
+    }
+}
+
+/// Default debounce interval of the reader state changes, in milliseconds. Disabled, so the
+/// reader changes are processed right away as before the debouncing was added.
+const DEFAULT_DEBOUNCE_MS: u64 = 0;
+
+/// Default time a removed card task gets to stop before it is aborted, in milliseconds.
+const DEFAULT_SHUTDOWN_GRACE_MS: u64 = 2000;
+
+/// Status word counted as success without success_status_words in the config.
+const DEFAULT_SUCCESS_STATUS_WORD: &str = "9000";
+
+/// Default pause after the card insertion before the first APDU, in milliseconds.
+const DEFAULT_INSERT_DELAY_MS: u64 = 100;
+
+/// Default ICCID read retries and the pause between them, in milliseconds.
+const DEFAULT_ICCID_RETRIES: u32 = 2;
+const DEFAULT_ICCID_RETRY_DELAY_MS: u64 = 500;
+
+/// Default time the reader monitor may be silent before the watchdog restarts it, in seconds.
+const DEFAULT_WATCHDOG_TIMEOUT_SECS: u64 = 60;
+
+/// Default number of card state events kept in the history.
+const DEFAULT_EVENT_HISTORY_SIZE: usize = 200;
+
+/// Default warning window before the card expiry, in days.
+const DEFAULT_EXPIRE_WARNING_DAYS: u64 = 30;
+
+/// Default circuit breaker settings: failures in a row, counting window and cooldown in seconds.
+const DEFAULT_BREAKER_FAILURES: u32 = 5;
+const DEFAULT_BREAKER_WINDOW_SECS: u64 = 60;
+const DEFAULT_BREAKER_COOLDOWN_SECS: u64 = 300;
+
 // UI Configuration structure, part of ConfigurationFile that contains data about how UI looks like.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct AppearanceConfig {
     pub dark_theme: DarkTheme,
 }
 
-/// Retrieves the configuration file path.
-/// This function constructs the path to the configuration file, creating the necessary directories if they do not exist.
-pub fn get_config_path() -> io::Result<PathBuf> {
+/// Time to wait for the data directory to answer. A redirected folder on an offline
+/// network share may hang instead of failing.
+const DATA_DIR_PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Data directory of the config and the logs, resolved once per run.
+static DATA_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+/// Checks that the directory can be created and written to, within `DATA_DIR_PROBE_TIMEOUT`.
+fn probe_data_dir(dir: &Path) -> io::Result<()> {
+    let dir = dir.to_path_buf();
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    // The probe runs on its own thread, a hanging share is left behind instead of blocking
+    std::thread::spawn(move || {
+        let result = fs::create_dir_all(&dir).and_then(|_| {
+            let probe = dir.join(".write_test");
+            File::create(&probe)?.write_all(b"ok")?;
+            fs::remove_file(&probe)
+        });
+        let _ = sender.send(result);
+    });
+
+    receiver
+        .recv_timeout(DATA_DIR_PROBE_TIMEOUT)
+        .unwrap_or_else(|_| {
+            Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "the path is unavailable (no answer)",
+            ))
+        })
+}
+
+/// Resolves the data directory of the config and the logs.
+/// The roaming app data directory is used when it is available. It may be redirected to
+/// OneDrive or a network share, so when it can't be written the local one is used instead.
+pub fn get_data_dir() -> io::Result<PathBuf> {
+    if let Some(dir) = DATA_DIR.get() {
+        return Ok(dir.clone());
+    }
+
     let app_handle = get_app_handle().ok_or_else(|| {
         io::Error::new(
             io::ErrorKind::Other,
             "App handle is not initialized; cannot resolve app_data_dir",
         )
     })?;
+    let path = app_handle.path();
+
+    let data_dir = path.app_data_dir().map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            format!("Failed to resolve app_data_dir: {}", e),
+        )
+    })?;
 
-    let mut config_path = app_handle
-        .path()
-        .app_data_dir()
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("Failed to resolve app_data_dir: {}", e)))?;
+    let dir = match probe_data_dir(&data_dir) {
+        Ok(()) => data_dir,
+        Err(e) => {
+            log::warn!("Data directory {:?} is unavailable: {}", data_dir, e);
+
+            let local_dir = path.app_local_data_dir().map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("Failed to resolve app_local_data_dir: {}", e),
+                )
+            })?;
+            if let Err(e) = probe_data_dir(&local_dir) {
+                log::error!("Local data directory {:?} is unavailable: {}", local_dir, e);
+                return Err(e);
+            }
 
-    log::debug!("Config directory path resolved to: {:?}", config_path);
+            let payload = NotificationPayload {
+                notification_type: "access".to_string(),
+                message: format!(
+                    "Folder {} is unavailable. The settings and logs are stored in {}.",
+                    data_dir.display(),
+                    local_dir.display()
+                ),
+            };
+            emit_notification_event("global-notification", payload);
 
-    if let Err(e) = fs::create_dir_all(&config_path) {
-        log::error!("Failed to create config directory {:?}: {}", config_path, e);
-        return Err(e);
-    }
+            local_dir
+        }
+    };
+
+    log::info!("Data directory: {:?}", dir);
+
+    Ok(DATA_DIR.get_or_init(|| dir).clone())
+}
+
+/// Retrieves the configuration file path.
+/// This function constructs the path to the configuration file, creating the necessary directories if they do not exist.
+pub fn get_config_path() -> io::Result<PathBuf> {
+    let mut config_path = get_data_dir()?;
 
     config_path.push("config.yaml");
 
@@ -99,9 +790,8 @@ fn load_config(
     Ok(config)
 }
 
-/// Saves the configuration to the file.
-/// This function serializes the configuration and writes it to the file.
-fn save_config(
+/// Serializes the configuration and writes it to the file.
+fn write_config(
     config_path: &Path,
     config: &ConfigurationFile,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
@@ -110,6 +800,102 @@ fn save_config(
     Ok(())
 }
 
+/// Returns why the configuration must not be written: the `read_only` flag of the
+/// configuration, or the file itself is read-only. None if the app may change it.
+fn config_managed_reason(config_path: &Path, config: &ConfigurationFile) -> Option<String> {
+    if config.read_only {
+        return Some("read_only is set in the configuration file".to_string());
+    }
+
+    fs::metadata(config_path)
+        .is_ok_and(|metadata| metadata.permissions().readonly())
+        .then(|| "the configuration file is read-only".to_string())
+}
+
+/// Refuses the change of a managed configuration, see `config_managed_reason`.
+/// Called by the commands before any side effect, e.g. the keyring, not only on save.
+fn ensure_config_mutable(
+    config_path: &Path,
+    config: &ConfigurationFile,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    match config_managed_reason(config_path, config) {
+        Some(reason) => {
+            log::warn!("Configuration change is refused: {}", reason);
+            Err(Box::new(ConfigManaged(reason)))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Saves the configuration to the file.
+/// This function serializes the configuration and writes it to the file.
+/// If the write fails, the frontend is notified with the error details.
+/// A managed configuration is not written, see `config_managed_reason`.
+fn save_config(
+    config_path: &Path,
+    config: &ConfigurationFile,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ensure_config_mutable(config_path, config)?;
+
+    let result = write_config(config_path, config);
+
+    if let Err(e) = &result {
+        log::error!("Failed to save configuration to {:?}: {}", config_path, e);
+
+        let payload = NotificationPayload {
+            notification_type: "config".to_string(),
+            message: format!("Failed to save configuration: {}", e),
+        };
+        emit_notification_event("global-notification", payload);
+    }
+
+    result
+}
+
+/// Finds another card number that already carries the given ICCID.
+/// Empty ICCIDs are not checked because they are not yet known.
+fn find_iccid_owner<'a>(
+    cards: &'a HashMap<String, CardConfig>,
+    card_number: &str,
+    iccid: &str,
+) -> Option<&'a str> {
+    if iccid.is_empty() {
+        return None;
+    }
+
+    cards
+        .iter()
+        .find(|(number, config)| number.as_str() != card_number && config.iccid == iccid)
+        .map(|(number, _)| number.as_str())
+}
+
+/// Checks the MQTT overrides of a card or the server: the topic prefix is a plain topic
+/// without wildcards and the QoS is 0, 1 or 2.
+fn validate_mqtt_overrides(topic_prefix: Option<&str>, qos: Option<u8>) -> Result<(), String> {
+    if let Some(topic_prefix) = topic_prefix {
+        if topic_prefix.trim().is_empty() {
+            return Err("Topic prefix is empty".to_string());
+        }
+        if topic_prefix.contains(|c| matches!(c, '+' | '#' | '\0')) {
+            return Err(format!(
+                "Topic prefix '{}' must not contain wildcards",
+                topic_prefix
+            ));
+        }
+        if topic_prefix.starts_with('/') || topic_prefix.ends_with('/') {
+            return Err(format!(
+                "Topic prefix '{}' must not start or end with '/'",
+                topic_prefix
+            ));
+        }
+    }
+
+    match qos {
+        Some(qos) if qos > 2 => Err(format!("QoS {} is invalid, expected 0, 1 or 2", qos)),
+        _ => Ok(()),
+    }
+}
+
 /// Updates the configuration with a new card.
 /// This function updates the configuration file with a new card's ATR and card number.
 fn update_card_config(
@@ -120,6 +906,23 @@ fn update_card_config(
     let mut config = load_config(config_path)?;
     log::debug!("Loaded configuration: {:?}", config);
 
+    // The ICCID must point to exactly one card number, otherwise the lookup by ICCID is ambiguous
+    if let Some(owner) = find_iccid_owner(&config.cards, card_number, &content.iccid) {
+        log::warn!(
+            "ICCID {} already belongs to the card {}. Card {} is not updated.",
+            content.iccid,
+            owner,
+            card_number
+        );
+        return Err(Box::new(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "ICCID {} is already assigned to the card {}",
+                content.iccid, owner
+            ),
+        )));
+    }
+
     // let mut needs_restart = false;
     let mut changed = false;
 
@@ -154,6 +957,10 @@ fn update_card_config(
             }
         }
         None => {
+            // The MQTT overrides of an existing card are kept, they are changed by set_card_topic
+            validate_mqtt_overrides(content.topic_prefix.as_deref(), content.qos)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
             // Add new card entirely
             log::debug!(
                 "Adding new card: card_number = {}, iccid = {}, name = {:?}, expire = {:?}",
@@ -162,6 +969,8 @@ fn update_card_config(
                 content.name,
                 content.expire
             );
+            let mut content = content;
+            content.enabled.get_or_insert(true);
             config.cards.insert(card_number.to_string(), content);
             // needs_restart = true;
             changed = true;
@@ -199,40 +1008,388 @@ fn update_card_config(
 /// Public function to update the configuration with a new card.
 /// This function is a Tauri command that updates the configuration file with a new card's ATR and card number.
 #[tauri::command]
-pub fn update_card(cardnumber: &str, content: CardConfig) -> bool {
-    let config_path = match get_config_path() {
-        Ok(path) => path,
-        Err(e) => {
-            log::error!("Failed to get config path: {}", e);
-            return false;
-        }
+pub fn update_card(cardnumber: &str, content: CardConfig) -> Result<(), ErrorInfo> {
+    let config_path = get_config_path().map_err(ErrorInfo::config_path)?;
+
+    update_card_config(&config_path, cardnumber, content)
+        .map_err(|e| ErrorInfo::config("Failed to update config", e))?;
+
+    log::info!("The card, {} is added to the configuration!", cardnumber);
+
+    Ok(())
+}
+
+/// Renames the card in the configuration.
+/// Only the name field is changed, the ICCID, expire date and the task pool are left untouched.
+fn rename_card_config(
+    config_path: &Path,
+    card_number: &str,
+    new_name: &str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut config = load_config(config_path)?;
+    ensure_config_mutable(config_path, &config)?;
+
+    let card = config.cards.get_mut(card_number).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Card {} not found in configuration", card_number),
+        )
+    })?;
+
+    // Empty name means that the custom name is removed
+    let new_name = new_name.trim();
+    card.name = if new_name.is_empty() {
+        None
+    } else {
+        Some(new_name.to_string())
     };
+    let card_config = card.clone();
 
-    match update_card_config(&config_path, cardnumber, content) {
-        Ok(_) => {
-            log::info!("The card, {} is added to the configuration!", cardnumber);
-            true
-        }
-        Err(e) => {
-            log::error!("Failed to update config: {}", e);
-            false
-        }
+    save_config(config_path, &config)?;
+    load_config_to_cache(&config)?;
+
+    emit_card_config_event(
+        "global-card-config-updated",
+        card_number.to_string(),
+        Some(card_config),
+    );
+
+    Ok(())
+}
+
+/// Public function to rename the card.
+/// This function is a Tauri command that changes only the custom name of the card.
+#[tauri::command]
+pub fn rename_card(card_number: String, new_name: String) -> Result<(), String> {
+    let config_path = get_config_path().map_err(|e| {
+        log::error!("Failed to get config path: {}", e);
+        format!("Failed to get config path: {}", e)
+    })?;
+
+    rename_card_config(&config_path, &card_number, &new_name).map_err(|e| {
+        log::error!("Failed to rename card {}: {}", card_number, e);
+        format!("Failed to rename card {}: {}", card_number, e)
+    })?;
+
+    log::info!("The card {} is renamed to '{}'", card_number, new_name);
+
+    Ok(())
+}
+
+/// Sets the ICCID of an existing card, so the inserted card is mapped to the card number.
+/// The other fields of the card are left untouched.
+fn set_card_iccid_config(
+    config_path: &Path,
+    card_number: &str,
+    iccid: &str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut config = load_config(config_path)?;
+    ensure_config_mutable(config_path, &config)?;
+
+    if iccid.is_empty() {
+        return Err(Box::new(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "ICCID is empty",
+        )));
+    }
+
+    // The ICCID must point to exactly one card number, otherwise the lookup by ICCID is ambiguous
+    if let Some(owner) = find_iccid_owner(&config.cards, card_number, iccid) {
+        return Err(Box::new(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("ICCID {} is already assigned to the card {}", iccid, owner),
+        )));
+    }
+
+    let card = config.cards.get_mut(card_number).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Card {} not found in configuration", card_number),
+        )
+    })?;
+
+    card.iccid = iccid.to_string();
+    let card_config = card.clone();
+
+    save_config(config_path, &config)?;
+    load_config_to_cache(&config)?;
+
+    emit_card_config_event(
+        "global-card-config-updated",
+        card_number.to_string(),
+        Some(card_config),
+    );
+
+    Ok(())
+}
+
+/// Public function to pair the card number with the ICCID of an inserted card.
+/// This function is a Tauri command, the readers are re-read so the card connects right away.
+#[tauri::command]
+pub fn set_card_iccid(card_number: String, iccid: String) -> Result<(), String> {
+    let config_path = get_config_path().map_err(|e| {
+        log::error!("Failed to get config path: {}", e);
+        format!("Failed to get config path: {}", e)
+    })?;
+
+    let iccid = iccid.trim().to_uppercase();
+    set_card_iccid_config(&config_path, &card_number, &iccid).map_err(|e| {
+        log::error!("Failed to set ICCID of card {}: {}", card_number, e);
+        format!("Failed to set ICCID of card {}: {}", card_number, e)
+    })?;
+
+    // The card may already be inserted, it is processed again with the new mapping
+    invalidate_reader_states();
+
+    log::info!("The card {} is paired with ICCID {}", card_number, iccid);
+
+    Ok(())
+}
+
+/// Stores the maintenance mode flag in the configuration, so it survives restarts.
+pub fn update_maintenance_config(
+    config_path: &Path,
+    enabled: bool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut config = load_config(config_path)?;
+    ensure_config_mutable(config_path, &config)?;
+
+    config
+        .smart_card
+        .get_or_insert_with(SmartCardConfig::default)
+        .maintenance = enabled;
+
+    save_config(config_path, &config)?;
+    load_config_to_cache(&config)?;
+
+    Ok(())
+}
+
+/// Enables or disables the broker connection of the card, the configuration is kept.
+fn set_card_enabled_config(
+    config_path: &Path,
+    card_number: &str,
+    enabled: bool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut config = load_config(config_path)?;
+    ensure_config_mutable(config_path, &config)?;
+
+    let card = config.cards.get_mut(card_number).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Card {} not found in configuration", card_number),
+        )
+    })?;
+
+    card.enabled = Some(enabled);
+    let card_config = card.clone();
+
+    save_config(config_path, &config)?;
+    load_config_to_cache(&config)?;
+
+    emit_card_config_event(
+        "global-card-config-updated",
+        card_number.to_string(),
+        Some(card_config),
+    );
+
+    Ok(())
+}
+
+/// Public function to enable or disable the card without deleting it.
+/// A disabled card keeps its configuration, but its broker connection is closed.
+/// Re-enabling syncs the readers, so an inserted card is connected again.
+#[tauri::command]
+pub async fn set_card_enabled(card_number: String, enabled: bool) -> Result<(), String> {
+    let config_path = get_config_path().map_err(|e| {
+        log::error!("Failed to get config path: {}", e);
+        format!("Failed to get config path: {}", e)
+    })?;
+
+    set_card_enabled_config(&config_path, &card_number, enabled).map_err(|e| {
+        log::error!("Failed to set enabled state of card {}: {}", card_number, e);
+        format!("Failed to set enabled state of card {}: {}", card_number, e)
+    })?;
+
+    if enabled {
+        invalidate_reader_states();
+    } else {
+        remove_connections(vec![card_number.clone()]).await;
     }
+
+    log::info!("The card {} is enabled: {}", card_number, enabled);
+
+    Ok(())
+}
+
+/// Pins the card to the preferred reader, None or an empty name removes the pin.
+fn set_card_reader_config(
+    config_path: &Path,
+    card_number: &str,
+    reader: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut config = load_config(config_path)?;
+    ensure_config_mutable(config_path, &config)?;
+
+    let card = config.cards.get_mut(card_number).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Card {} not found in configuration", card_number),
+        )
+    })?;
+
+    card.reader = reader
+        .map(str::trim)
+        .filter(|reader| !reader.is_empty())
+        .map(str::to_string);
+    let card_config = card.clone();
+
+    save_config(config_path, &config)?;
+    load_config_to_cache(&config)?;
+
+    emit_card_config_event(
+        "global-card-config-updated",
+        card_number.to_string(),
+        Some(card_config),
+    );
+
+    Ok(())
+}
+
+/// Public function to pin the card to a reader, e.g. from a dropdown filled by
+/// `list_readers_for_selection`. The card is re-evaluated in the reader it is inserted in.
+#[tauri::command]
+pub async fn set_card_reader(card_number: String, reader: Option<String>) -> Result<(), String> {
+    let config_path = get_config_path().map_err(|e| {
+        log::error!("Failed to get config path: {}", e);
+        format!("Failed to get config path: {}", e)
+    })?;
+
+    set_card_reader_config(&config_path, &card_number, reader.as_deref()).map_err(|e| {
+        log::error!("Failed to set reader of card {}: {}", card_number, e);
+        format!("Failed to set reader of card {}: {}", card_number, e)
+    })?;
+
+    remove_connections(vec![card_number.clone()]).await;
+    invalidate_reader_states();
+
+    log::info!("The card {} is pinned to reader {:?}", card_number, reader);
+
+    Ok(())
+}
+
+/// Sets the topic prefix and the QoS overrides of the card, None falls back to the server settings.
+fn set_card_topic_config(
+    config_path: &Path,
+    card_number: &str,
+    topic_prefix: Option<&str>,
+    qos: Option<u8>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let topic_prefix = topic_prefix
+        .map(str::trim)
+        .filter(|topic_prefix| !topic_prefix.is_empty());
+    validate_mqtt_overrides(topic_prefix, qos)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let mut config = load_config(config_path)?;
+    ensure_config_mutable(config_path, &config)?;
+
+    let card = config.cards.get_mut(card_number).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Card {} not found in configuration", card_number),
+        )
+    })?;
+
+    card.topic_prefix = topic_prefix.map(str::to_string);
+    card.qos = qos;
+    let card_config = card.clone();
+
+    save_config(config_path, &config)?;
+    load_config_to_cache(&config)?;
+
+    emit_card_config_event(
+        "global-card-config-updated",
+        card_number.to_string(),
+        Some(card_config),
+    );
+
+    Ok(())
+}
+
+/// Public function to bind the card to another backend channel. The card is reconnected,
+/// so the new subscription is made right away.
+#[tauri::command]
+pub async fn set_card_topic(
+    card_number: String,
+    topic_prefix: Option<String>,
+    qos: Option<u8>,
+) -> Result<(), String> {
+    let config_path = get_config_path().map_err(|e| {
+        log::error!("Failed to get config path: {}", e);
+        format!("Failed to get config path: {}", e)
+    })?;
+
+    set_card_topic_config(&config_path, &card_number, topic_prefix.as_deref(), qos).map_err(
+        |e| {
+            log::error!("Failed to set topic of card {}: {}", card_number, e);
+            format!("Failed to set topic of card {}: {}", card_number, e)
+        },
+    )?;
+
+    remove_connections(vec![card_number.clone()]).await;
+    invalidate_reader_states();
+
+    log::info!(
+        "The card {} uses topic prefix {:?} and QoS {:?}",
+        card_number,
+        topic_prefix,
+        qos
+    );
+
+    Ok(())
 }
 
 /// Updates the server address in the configuration.
 /// This function updates the configuration file with a new server address.
+/// The credentials are changed only if the username is given, an empty username removes them.
 pub fn update_server_config(
     config_path: &Path,
     host: &str,
     ident: &str,
     theme: &str,
+    username: Option<&str>,
+    password: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let mut config = load_config(config_path)?;
+    ensure_config_mutable(config_path, &config)?;
+    let old_ident = config.ident.clone().unwrap_or_default();
+
+    // Keep the other server settings, only the host is changed from the frontend
+    let mut server = config.server.take().unwrap_or_default();
+    server.host = host.to_string();
+
+    match username {
+        Some("") => set_server_credentials(&mut server, &old_ident, None),
+        Some(username) => {
+            // The old entry is removed first, the ident may change
+            set_server_credentials(&mut server, &old_ident, None);
+            let credentials = Credentials {
+                username: username.to_string(),
+                password: password.unwrap_or_default().to_string(),
+            };
+            set_server_credentials(&mut server, ident, Some(credentials));
+        }
+        None if server.credentials_in_keyring && old_ident != ident => {
+            // The keyring entry is keyed by the ident, so it follows the ident change
+            let credentials = load_from_keyring(&old_ident);
+            set_server_credentials(&mut server, &old_ident, None);
+            set_server_credentials(&mut server, ident, credentials);
+        }
+        None => {}
+    }
 
-    config.server = Some(ServerConfig {
-        host: host.to_string(),
-    });
+    config.server = Some(server);
     config.ident = Some(ident.to_string());
     config.appearance = Some(AppearanceConfig {
         dark_theme: match theme {
@@ -246,6 +1403,247 @@ pub fn update_server_config(
     save_config(config_path, &config)?;
     load_config_to_cache(&config)?;
 
+    store_ident(ident);
+
+    Ok(())
+}
+
+/// Updates the MQTT settings of the server section, the other settings are kept.
+/// `None` keeps the current value. Returns whether the keep-alive or the channel capacity
+/// (used by every connection) and whether the QoS (used by the cards) are changed.
+pub fn update_mqtt_settings_config(
+    config_path: &Path,
+    keep_alive_secs: Option<u64>,
+    qos: Option<u8>,
+    channel_capacity: Option<usize>,
+) -> Result<(bool, bool), Box<dyn std::error::Error + Send + Sync>> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+
+    if let Some(keep_alive_secs) = keep_alive_secs {
+        if !(MIN_KEEP_ALIVE_SECS..=MAX_KEEP_ALIVE_SECS).contains(&keep_alive_secs) {
+            return Err(invalid(format!(
+                "Keep-alive {} s is invalid, expected {} to {} s",
+                keep_alive_secs, MIN_KEEP_ALIVE_SECS, MAX_KEEP_ALIVE_SECS
+            ))
+            .into());
+        }
+    }
+    validate_mqtt_overrides(None, qos).map_err(invalid)?;
+    if let Some(channel_capacity) = channel_capacity {
+        if !(1..=MAX_CHANNEL_CAPACITY).contains(&channel_capacity) {
+            return Err(invalid(format!(
+                "Channel capacity {} is invalid, expected 1 to {}",
+                channel_capacity, MAX_CHANNEL_CAPACITY
+            ))
+            .into());
+        }
+    }
+
+    let mut config = load_config(config_path)?;
+    ensure_config_mutable(config_path, &config)?;
+    let server = config
+        .server
+        .as_mut()
+        .ok_or_else(|| invalid("Server is not configured".to_string()))?;
+
+    let connection_changed = (keep_alive_secs.is_some() && keep_alive_secs != server.keep_alive)
+        || (channel_capacity.is_some() && channel_capacity != server.channel_capacity);
+    let qos_changed = qos.is_some() && qos != server.qos;
+
+    server.keep_alive = keep_alive_secs.or(server.keep_alive);
+    server.qos = qos.or(server.qos);
+    server.channel_capacity = channel_capacity.or(server.channel_capacity);
+
+    save_config(config_path, &config)?;
+    load_config_to_cache(&config)?;
+
+    Ok((connection_changed, qos_changed))
+}
+
+/// Stores the broker credentials in the keyring, or in the plaintext config if the keyring
+/// is not available. `None` removes the credentials.
+fn set_server_credentials(
+    server: &mut ServerConfig,
+    ident: &str,
+    credentials: Option<Credentials>,
+) {
+    let Some(credentials) = credentials else {
+        if server.credentials_in_keyring {
+            delete_from_keyring(ident);
+        }
+        server.username = None;
+        server.password = None;
+        server.credentials_in_keyring = false;
+        return;
+    };
+
+    match store_in_keyring(ident, &credentials) {
+        Ok(()) => {
+            server.username = None;
+            server.password = None;
+            server.credentials_in_keyring = true;
+        }
+        Err(e) => {
+            log::warn!(
+                "Keyring is not available: {}. The credentials are stored in the config file.",
+                e
+            );
+            server.username = Some(credentials.username);
+            server.password = Some(credentials.password);
+            server.credentials_in_keyring = false;
+        }
+    }
+}
+
+/// Moves the plaintext credentials of the configuration to the keyring.
+/// The credentials stay in the configuration if the keyring is not available.
+fn migrate_credentials_to_keyring(config: &mut ConfigurationFile) {
+    let ident = config.ident.clone().unwrap_or_default();
+    let Some(server) = config.server.as_mut() else {
+        return;
+    };
+
+    if server.credentials_in_keyring || ident.is_empty() {
+        return;
+    }
+
+    if let Some(username) = server.username.clone() {
+        let credentials = Credentials {
+            username,
+            password: server.password.clone().unwrap_or_default(),
+        };
+        set_server_credentials(server, &ident, Some(credentials));
+
+        if server.credentials_in_keyring {
+            log::info!("The broker credentials are moved to the keyring");
+        }
+    }
+}
+
+/// Re-reads the server, ident and appearance settings from the file into the cache.
+/// The connections are restarted only if the host or the ident changed.
+async fn reload_server_config_from_file(
+    config_path: &Path,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let config = load_config(config_path)?;
+
+    let old_host = get_from_cache(CacheSection::Server, "host");
+    let old_ident = get_from_cache(CacheSection::Ident, "ident");
+
+    {
+        let mut cache = CACHE.lock().unwrap();
+        cache.server = config.server.clone();
+        cache.ident = config.ident.clone();
+        cache.appearance = config.appearance.clone();
+    }
+
+    let new_host = get_from_cache(CacheSection::Server, "host");
+    let new_ident = get_from_cache(CacheSection::Ident, "ident");
+
+    if !new_ident.is_empty() {
+        store_ident(&new_ident);
+    }
+
+    if old_host != new_host {
+        log::info!(
+            "Server host is changed from '{}' to '{}'",
+            old_host,
+            new_host
+        );
+    }
+    if old_ident != new_ident {
+        log::info!("Ident is changed from '{}' to '{}'", old_ident, new_ident);
+    }
+
+    // Every connection belongs to the old host. The ident is the client ID of the app
+    // connection and a part of the client IDs of the cards, see `compose_client_id`.
+    if old_host != new_host || old_ident != new_ident {
+        remove_connections_all().await;
+        invalidate_reader_states();
+        async_runtime::spawn(app_connection());
+    }
+
+    if let Some(app_handle) = get_app_handle() {
+        if let Err(e) = emit_global_config_server(&app_handle) {
+            log::error!("Failed to emit global config server: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Public function to apply the server section of the manually edited configuration file.
+/// This function is a Tauri command that returns an error if the file can not be parsed.
+#[tauri::command]
+pub async fn reload_server_config() -> Result<(), String> {
+    let config_path = get_config_path().map_err(|e| {
+        log::error!("Failed to get config path: {}", e);
+        format!("Failed to get config path: {}", e)
+    })?;
+
+    reload_server_config_from_file(&config_path)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to reload server config: {}", e);
+            format!("Failed to reload server config: {}", e)
+        })?;
+
+    log::info!("Server config is reloaded from {:?}", config_path);
+
+    Ok(())
+}
+
+/// Re-reads the configuration file into the cache and re-emits the configuration to the frontend.
+/// The cache is left untouched if the file can not be parsed.
+fn refresh_cache_from_file(
+    config_path: &Path,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let config = load_config(config_path)?;
+
+    // Cards that are gone from the file, the frontend drops them
+    let removed_cards: Vec<String> = get_cards_from_cache()
+        .into_keys()
+        .filter(|card_number| !config.cards.contains_key(card_number))
+        .collect();
+
+    load_config_to_cache(&config)?;
+
+    for card_number in removed_cards {
+        emit_card_config_event("global-card-config-updated", card_number, None);
+    }
+    for (card_number, card_config) in &config.cards {
+        emit_card_config_event(
+            "global-card-config-updated",
+            card_number.clone(),
+            Some(card_config.clone()),
+        );
+    }
+
+    if let Some(app_handle) = get_app_handle() {
+        if let Err(e) = emit_global_config_server(&app_handle) {
+            log::error!("Failed to emit global config server: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Public function to resynchronize the cache and the frontend with the configuration file.
+/// This function is a Tauri command that returns an error if the file can not be parsed.
+#[tauri::command]
+pub fn refresh_cache() -> Result<(), String> {
+    let config_path = get_config_path().map_err(|e| {
+        log::error!("Failed to get config path: {}", e);
+        format!("Failed to get config path: {}", e)
+    })?;
+
+    refresh_cache_from_file(&config_path).map_err(|e| {
+        log::error!("Failed to refresh the cache: {}", e);
+        format!("Failed to refresh the cache: {}", e)
+    })?;
+
+    log::info!("Cache is refreshed from {:?}", config_path);
+
     Ok(())
 }
 
@@ -289,17 +1687,8 @@ pub async fn remove_card_from_config(
 
         emit_card_config_event("global-card-config-updated", card_number.to_string(), None);
 
-        #[cfg(target_os = "linux")]
-        {
-            // "Super hack" to reload card states and trigger an event to update readers.
-
-            use crate::smart_card::manual_sync_cards;
-            use tokio::time::sleep;
-            use tokio::time::Duration;
-
-            sleep(Duration::from_millis(100)).await;
-            manual_sync_cards(card_number.to_string(), false).await;
-        }
+        // The card stays inserted, so the readers are re-read to update the card rows
+        invalidate_reader_states();
 
         Ok(())
     } else {
@@ -314,25 +1703,77 @@ pub async fn remove_card_from_config(
 /// Public function to update the server address in the configuration.
 /// This function is a Tauri command that updates the configuration file with a new server address.
 #[tauri::command]
-pub fn update_server(host: &str, ident: &str, theme: &str) -> bool {
-    let config_path = match get_config_path() {
-        Ok(path) => path,
-        Err(e) => {
-            log::error!("Failed to get config path: {}", e);
-            return false;
+pub fn update_server(
+    host: &str,
+    ident: &str,
+    theme: &str,
+    username: Option<String>,
+    password: Option<String>,
+) -> Result<(), ErrorInfo> {
+    let config_path = get_config_path().map_err(ErrorInfo::config_path)?;
+
+    update_server_config(
+        &config_path,
+        host,
+        ident,
+        theme,
+        username.as_deref(),
+        password.as_deref(),
+    )
+    .map_err(|e| ErrorInfo::config("Failed to update server address", e))?;
+
+    log::info!("The server address is updated to '{}'.", host);
+
+    // The theme is applied right away, not only at the next startup
+    if let Some(app_handle) = get_app_handle() {
+        if let Err(e) = emit_global_config_server(&app_handle) {
+            log::error!("Failed to emit global config server: {}", e);
         }
-    };
+    }
+
+    Ok(())
+}
+
+/// Updates only the keep-alive, the QoS and the channel capacity of the broker connections,
+/// so tuning them doesn't resend the host and the ident. The affected connections are
+/// re-established with the new settings.
+#[tauri::command]
+pub async fn update_mqtt_settings(
+    keep_alive_secs: Option<u64>,
+    qos: Option<u8>,
+    channel_capacity: Option<usize>,
+) -> Result<(), ErrorInfo> {
+    let config_path = get_config_path().map_err(ErrorInfo::config_path)?;
+
+    let (connection_changed, qos_changed) =
+        update_mqtt_settings_config(&config_path, keep_alive_secs, qos, channel_capacity)
+            .map_err(|e| ErrorInfo::config("Failed to update MQTT settings", e))?;
+
+    log::info!(
+        "MQTT settings are updated: keep-alive {:?}, QoS {:?}, channel capacity {:?}",
+        keep_alive_secs,
+        qos,
+        channel_capacity
+    );
+
+    if connection_changed {
+        // Every connection is created with these settings
+        remove_connections_all().await;
+        invalidate_reader_states();
+        async_runtime::spawn(app_connection());
+    } else if qos_changed {
+        // The app connection doesn't use the QoS of the cards
+        remove_card_connections().await;
+        invalidate_reader_states();
+    }
 
-    match update_server_config(&config_path, host, ident, theme) {
-        Ok(_) => {
-            log::info!("The server address is updated to '{}'.", host);
-            true
-        }
-        Err(e) => {
-            log::error!("Failed to update server address: {}", e);
-            false
+    if let Some(app_handle) = get_app_handle() {
+        if let Err(e) = emit_global_config_server(&app_handle) {
+            log::error!("Failed to emit global config server: {}", e);
         }
     }
+
+    Ok(())
 }
 
 /*
@@ -345,9 +1786,46 @@ pub fn update_server(host: &str, ident: &str, theme: &str) -> bool {
 #[derive(Default, Debug)]
 pub struct CacheConfigData {
     pub cards: HashMap<String, CardConfig>,
+    pub iccid_index: HashMap<String, String>, // Reverse index ICCID => card number for O(1) lookups.
     pub server: Option<ServerConfig>,
     pub ident: Option<String>,
     pub appearance: Option<AppearanceConfig>,
+    pub smart_card: Option<SmartCardConfig>,
+    pub allowed_cards: Option<Vec<String>>,
+    pub min_version: Option<String>,
+    pub config_check_interval_secs: Option<u64>,
+}
+
+impl CacheConfigData {
+    /// Returns the sections loaded from the configuration file as JSON, keyed by the section
+    /// name, so the cache can be compared with the file, see `check_config_integrity`.
+    fn sections(&self) -> Vec<(&'static str, serde_json::Value)> {
+        let to_json = |value: Result<serde_json::Value, serde_json::Error>| {
+            value.unwrap_or_else(|e| serde_json::Value::String(e.to_string()))
+        };
+
+        vec![
+            ("cards", to_json(serde_json::to_value(&self.cards))),
+            ("server", to_json(serde_json::to_value(&self.server))),
+            ("ident", to_json(serde_json::to_value(&self.ident))),
+            (
+                "appearance",
+                to_json(serde_json::to_value(&self.appearance)),
+            ),
+            (
+                "smart_card",
+                to_json(serde_json::to_value(&self.smart_card)),
+            ),
+            (
+                "allowed_cards",
+                to_json(serde_json::to_value(&self.allowed_cards)),
+            ),
+            (
+                "min_version",
+                to_json(serde_json::to_value(&self.min_version)),
+            ),
+        ]
+    }
 }
 
 lazy_static! {
@@ -371,32 +1849,26 @@ pub fn get_from_cache(section: CacheSection, key: &str) -> String {
     let cache = CACHE.lock().unwrap();
 
     log::debug!("Accessing cache section: {:?}, key: {}", section, key);
-    log::debug!("Current cache state: {:?}", *cache); // Покажет всё, если у `CacheConfigData` реализован Debug
+    log::trace!("Current cache state: {:?}", *cache); // Покажет всё, если у `CacheConfigData` реализован Debug
 
     match section {
         CacheSection::Cards => {
             log::debug!("Looking up by ICCID: {}", key);
 
-            for (card_number, config) in &cache.cards {
-                log::debug!(
-                    "Cache entry -> card_number: {}, iccid: {}, expire: {:?}",
-                    card_number,
-                    config.iccid,
-                    config.expire
-                );
-
-                if config.iccid == key {
+            match cache.iccid_index.get(key) {
+                Some(card_number) => {
                     log::debug!(
                         "Match found: ICCID {} corresponds to card_number {}",
                         key,
                         card_number
                     );
-                    return card_number.clone();
+                    card_number.clone()
+                }
+                None => {
+                    log::debug!("No ICCID match found for: {}", key);
+                    "".to_string()
                 }
             }
-
-            log::debug!("No ICCID match found for: {}", key);
-            "".to_string()
         }
 
         CacheSection::Server => {
@@ -446,6 +1918,254 @@ pub fn get_from_cache(section: CacheSection, key: &str) -> String {
     }
 }
 
+/// Retrieves all configured cards from the cache.
+pub fn get_cards_from_cache() -> HashMap<String, CardConfig> {
+    let cache = CACHE.lock().unwrap();
+    cache.cards.clone()
+}
+
+/// Retrieves the server settings from the cache.
+/// Returns the default settings if the section is absent in the configuration.
+pub fn get_server_config() -> ServerConfig {
+    let cache = CACHE.lock().unwrap();
+    cache.server.clone().unwrap_or_default()
+}
+
+/// Retrieves the broker credentials, from the keyring or from the plaintext config.
+pub fn get_server_credentials() -> Option<Credentials> {
+    let server = get_server_config();
+
+    if server.credentials_in_keyring {
+        let ident = get_from_cache(CacheSection::Ident, "ident");
+        return load_from_keyring(&ident);
+    }
+
+    server.username.map(|username| Credentials {
+        username,
+        password: server.password.unwrap_or_default(),
+    })
+}
+
+/// Retrieves the smart card settings from the cache.
+/// Returns the default settings if the section is absent in the configuration.
+pub fn get_smart_card_config() -> SmartCardConfig {
+    let cache = CACHE.lock().unwrap();
+    cache.smart_card.clone().unwrap_or_default()
+}
+
+/// Returns whether the card is enabled. Unknown cards are treated as enabled.
+pub fn is_card_enabled(card_number: &str) -> bool {
+    let cache = CACHE.lock().unwrap();
+    cache
+        .cards
+        .get(card_number)
+        .map_or(true, CardConfig::is_enabled)
+}
+
+/// Returns whether the card may connect to the broker. An absent or empty list allows all cards.
+pub fn is_card_allowed(card_number: &str) -> bool {
+    let cache = CACHE.lock().unwrap();
+    match &cache.allowed_cards {
+        Some(allowed) if !allowed.is_empty() => allowed
+            .iter()
+            .any(|allowed| allowed.trim().eq_ignore_ascii_case(card_number)),
+        _ => true,
+    }
+}
+
+/// Returns whether the card may be processed in the given reader.
+/// Cards without a preferred reader are processed in any reader.
+pub fn is_preferred_reader(card_number: &str, reader_name: &str) -> bool {
+    let cache = CACHE.lock().unwrap();
+    cache
+        .cards
+        .get(card_number)
+        .and_then(|card| card.reader.as_deref())
+        .map_or(true, |reader| reader == reader_name)
+}
+
+/// Returns the topic prefix and the QoS of the card: the card overrides first, then the server
+/// settings. The topic prefix falls back to the default of the server, see
+/// `ServerConfig::topic_prefix`, a None QoS means the built-in default.
+pub fn get_card_mqtt_settings(card_number: &str) -> (String, Option<u8>) {
+    let cache = CACHE.lock().unwrap();
+    let card = cache.cards.get(card_number);
+    let server = cache.server.as_ref();
+
+    let topic_prefix = card
+        .and_then(|card| card.topic_prefix.clone())
+        .unwrap_or_else(|| {
+            server.map_or_else(
+                || DEFAULT_TOPIC_PREFIX.to_string(),
+                ServerConfig::topic_prefix,
+            )
+        });
+    let qos = card
+        .and_then(|card| card.qos)
+        .or_else(|| server.and_then(|server| server.qos));
+
+    (topic_prefix, qos)
+}
+
+/// Returns the interval of the config integrity check, None if it is disabled.
+fn get_config_check_interval() -> Option<Duration> {
+    let cache = CACHE.lock().unwrap();
+    cache
+        .config_check_interval_secs
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+}
+
+/// Checks the cache against the configuration file forever, see `check_config_integrity`.
+/// While the check is disabled the setting is looked up again every `CONFIG_CHECK_IDLE_INTERVAL`.
+pub async fn config_integrity_monitor() -> ! {
+    loop {
+        match get_config_check_interval() {
+            Some(interval) => {
+                tokio::time::sleep(interval).await;
+                check_config_integrity().await;
+            }
+            None => tokio::time::sleep(CONFIG_CHECK_IDLE_INTERVAL).await,
+        }
+    }
+}
+
+/// Returns the sections of the cache that differ from the configuration file.
+fn drifted_sections(config: &ConfigurationFile) -> Vec<&'static str> {
+    let on_disk = CacheConfigData {
+        cards: config.cards.clone(),
+        server: config.server.clone(),
+        ident: config.ident.clone(),
+        appearance: config.appearance.clone(),
+        smart_card: config.smart_card.clone().map(SmartCardConfig::validated),
+        allowed_cards: config.allowed_cards.clone(),
+        min_version: config.min_version.clone(),
+        ..Default::default()
+    }
+    .sections();
+    let cached = CACHE.lock().unwrap().sections();
+
+    cached
+        .into_iter()
+        .zip(on_disk)
+        .filter(|((_, cached), (_, on_disk))| cached != on_disk)
+        .map(|((section, _), _)| section)
+        .collect()
+}
+
+/// Reloads the configuration file and compares it with the cache. The cache and the on-disk
+/// configuration are changed in several places, a partially failed write makes them drift.
+/// On a difference the user is notified and the cache is reconciled to the file.
+pub async fn check_config_integrity() {
+    let Ok(config_path) = get_config_path() else {
+        return;
+    };
+    let config = match load_config(&config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            log::warn!(
+                "Config integrity check skipped, the file can't be read: {}",
+                e
+            );
+            return;
+        }
+    };
+
+    if drifted_sections(&config).is_empty() {
+        return;
+    }
+
+    // A save may be in progress between the file write and the cache update, check again
+    tokio::time::sleep(CONFIG_CHECK_RECHECK_DELAY).await;
+    let config = match load_config(&config_path) {
+        Ok(config) => config,
+        Err(_) => return,
+    };
+    let drifted = drifted_sections(&config);
+    if drifted.is_empty() {
+        return;
+    }
+
+    log::warn!(
+        "Config cache differs from {:?} in: {}. The cache is reloaded from the file.",
+        config_path,
+        drifted.join(", ")
+    );
+    let payload = NotificationPayload {
+        notification_type: "config".to_string(),
+        message: format!(
+            "Configuration in use differed from the file ({}), the file is applied",
+            drifted.join(", ")
+        ),
+    };
+    emit_notification_event("global-notification", payload);
+
+    // The server section restarts the connections if needed, the rest is refreshed as a whole
+    if let Err(e) = reload_server_config_from_file(&config_path).await {
+        log::error!("Failed to reconcile the server config: {}", e);
+    }
+    if let Err(e) = refresh_cache_from_file(&config_path) {
+        log::error!("Failed to reconcile the config cache: {}", e);
+    }
+    invalidate_reader_states();
+}
+
+/// Whether the configuration may be changed from the UI, see `config_managed_reason`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigMutability {
+    pub read_only: bool,        // The edit controls are disabled.
+    pub reason: Option<String>, // Why the configuration is managed.
+}
+
+#[tauri::command]
+pub fn get_config_mutability() -> Result<ConfigMutability, ErrorInfo> {
+    let config_path = get_config_path().map_err(ErrorInfo::config_path)?;
+    let config = load_config(&config_path)
+        .map_err(|e| ErrorInfo::config("Failed to load configuration", e))?;
+
+    let reason = config_managed_reason(&config_path, &config);
+    Ok(ConfigMutability {
+        read_only: reason.is_some(),
+        reason,
+    })
+}
+
+/// Returns the minimum supported version of the application, if configured.
+pub fn get_min_version() -> Option<String> {
+    let cache = CACHE.lock().unwrap();
+    cache.min_version.clone()
+}
+
+/// Returns the configuration file as YAML with the broker credentials masked, for the support.
+pub fn redacted_config_yaml() -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let config_path = get_config_path()?;
+    let mut config = load_config(&config_path)?;
+
+    if let Some(server) = config.server.as_mut() {
+        for secret in [&mut server.username, &mut server.password] {
+            if secret.is_some() {
+                *secret = Some("<redacted>".to_string());
+            }
+        }
+    }
+
+    Ok(serde_yaml::to_string(&config)?)
+}
+
+/// Returns the log level overrides per module path, read from the config file directly.
+/// The logging is set up before the config is loaded to the cache, so the cache can't be used.
+/// Empty if the config file doesn't exist yet or can't be read.
+pub fn get_log_levels() -> HashMap<String, String> {
+    let Ok(config_path) = get_config_path() else {
+        return HashMap::new();
+    };
+
+    load_config(&config_path)
+        .ok()
+        .and_then(|config| config.log_levels)
+        .unwrap_or_default()
+}
+
 /// Splits a host string into host and port components.
 ///
 /// This function takes a string containing a host and port separated by a colon (e.g., "example.com:8080"),
@@ -471,12 +2191,33 @@ pub fn load_config_to_cache(
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     log::debug!("load_config_to_cache");
 
+    // Build the reverse index. Cards without ICCID can not be found by ICCID anyway.
+    let mut iccid_index = HashMap::with_capacity(config.cards.len());
+    for (card_number, card_config) in &config.cards {
+        if card_config.iccid.is_empty() {
+            continue;
+        }
+        if let Some(previous) = iccid_index.insert(card_config.iccid.clone(), card_number.clone()) {
+            log::warn!(
+                "ICCID {} is assigned to several cards: {} and {}",
+                card_config.iccid,
+                previous,
+                card_number
+            );
+        }
+    }
+
     let mut cache = CACHE.lock().unwrap();
     *cache = CacheConfigData {
         cards: config.cards.clone(),
+        iccid_index,
         server: config.server.clone(),
         ident: config.ident.clone(),
         appearance: config.appearance.clone(),
+        smart_card: config.smart_card.clone().map(SmartCardConfig::validated),
+        allowed_cards: config.allowed_cards.clone(),
+        min_version: config.min_version.clone(),
+        config_check_interval_secs: config.config_check_interval_secs,
     };
 
     // trace_cache(&*cache);
@@ -524,27 +2265,206 @@ fn generate_ident() -> String {
     format!("TBA{:013}", micros % 1_000_000_000_000u128)
 }
 
+/// Retrieves the path of the file that keeps the ident outside of `config.yaml`.
+/// The file is stored in the platform data directory, next to (not inside) the app data directory,
+/// so that it survives reinstalls of the application and resets of the configuration.
+fn get_ident_store_path() -> io::Result<PathBuf> {
+    let app_handle = get_app_handle().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            "App handle is not initialized; cannot resolve data_dir",
+        )
+    })?;
+
+    let mut ident_path = app_handle.path().data_dir().map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            format!("Failed to resolve data_dir: {}", e),
+        )
+    })?;
+
+    ident_path.push("tacho-card-client");
+    fs::create_dir_all(&ident_path)?;
+    ident_path.push("ident");
+
+    Ok(ident_path)
+}
+
+/// Reads the previously stored ident, if any.
+fn read_stored_ident() -> Option<String> {
+    let ident_path = match get_ident_store_path() {
+        Ok(path) => path,
+        Err(e) => {
+            log::warn!("Failed to resolve ident store path: {}", e);
+            return None;
+        }
+    };
+
+    let ident = fs::read_to_string(&ident_path).ok()?.trim().to_string();
+    if ident.is_empty() {
+        return None;
+    }
+
+    log::debug!("Stored ident {} is found in {:?}", ident, ident_path);
+    Some(ident)
+}
+
+/// Stores the ident in the stable location so it can be reused after a reinstall.
+fn store_ident(ident: &str) {
+    if ident.is_empty() {
+        return;
+    }
+
+    let result = get_ident_store_path().and_then(|path| fs::write(path, ident));
+    if let Err(e) = result {
+        log::warn!("Failed to store ident {}: {}", ident, e);
+    }
+}
+
+/// Returns the stored ident or generates (and stores) a new one on the first run.
+fn resolve_ident() -> String {
+    if let Some(ident) = read_stored_ident() {
+        log::info!("Reusing stored ident: {}", ident);
+        return ident;
+    }
+
+    let ident = generate_ident();
+    log::info!("New ident is generated: {}", ident);
+    store_ident(&ident);
+    ident
+}
+
+/// Checks the ident can be used in the MQTT client IDs and topics: letters, digits, '-' and '_'.
+fn validate_ident(ident: &str) -> Result<(), String> {
+    if ident.is_empty() {
+        return Err("Ident is empty".to_string());
+    }
+    if ident.len() > MAX_IDENT_LEN {
+        return Err(format!("Ident is longer than {} characters", MAX_IDENT_LEN));
+    }
+    match ident
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || *c == '-' || *c == '_'))
+    {
+        Some(c) => Err(format!("Ident must not contain '{}'", c)),
+        None => Ok(()),
+    }
+}
+
+/// Sets the ident of the machine and returns the previous one.
+/// The broker credentials in the keyring are keyed by the ident, so they are moved along.
+fn set_ident_config(
+    config_path: &Path,
+    ident: &str,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    validate_ident(ident).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let mut config = load_config(config_path)?;
+    let old_ident = config.ident.clone().unwrap_or_default();
+    if old_ident == ident {
+        return Ok(old_ident);
+    }
+    ensure_config_mutable(config_path, &config)?;
+
+    // The app connection shares the task pool with the cards, keyed by the ident and the card numbers
+    if config.cards.contains_key(ident) {
+        return Err(Box::new(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("Ident {} is the number of a configured card", ident),
+        )));
+    }
+
+    let reserved = config.reserved_idents.as_ref().is_some_and(|idents| {
+        idents
+            .iter()
+            .any(|reserved| reserved.eq_ignore_ascii_case(ident))
+    });
+    if reserved {
+        match config.ident_duplicates {
+            IdentDuplicatePolicy::Reject => {
+                return Err(Box::new(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("Ident {} is already used by another machine", ident),
+                )));
+            }
+            IdentDuplicatePolicy::Warn => {
+                log::warn!("Ident {} is already used by another machine", ident);
+            }
+        }
+    }
+
+    if let Some(server) = config.server.as_mut() {
+        if server.credentials_in_keyring {
+            let credentials = load_from_keyring(&old_ident);
+            set_server_credentials(server, &old_ident, None);
+            set_server_credentials(server, ident, credentials);
+        }
+    }
+
+    config.ident = Some(ident.to_string());
+
+    save_config(config_path, &config)?;
+    load_config_to_cache(&config)?;
+    store_ident(ident);
+
+    Ok(old_ident)
+}
+
+/// Public function to assign a specific ident to the machine, e.g. a standardized fleet name.
+/// The app connection and the cards are reconnected with the new client IDs.
+#[tauri::command]
+pub async fn set_ident(ident: String) -> Result<(), ErrorInfo> {
+    let config_path = get_config_path().map_err(ErrorInfo::config_path)?;
+    let ident = ident.trim();
+
+    let old_ident = set_ident_config(&config_path, ident)
+        .map_err(|e| ErrorInfo::config("Failed to set ident", e))?;
+    if old_ident == ident {
+        return Ok(());
+    }
+
+    // The ident is the client ID of the app connection and a part of the client IDs and the
+    // user properties of the cards, see `compose_client_id`
+    remove_connections_all().await;
+    invalidate_reader_states();
+    async_runtime::spawn(app_connection());
+
+    if let Some(app_handle) = get_app_handle() {
+        if let Err(e) = emit_global_config_server(&app_handle) {
+            log::error!("Failed to emit global config server: {}", e);
+        }
+    }
+
+    log::info!("Ident is changed from '{}' to '{}'", old_ident, ident);
+
+    Ok(())
+}
+
+/// Public function to get the active ident.
+/// This function is a Tauri command that exposes the ident to the frontend in read-only mode.
+#[tauri::command]
+pub fn get_ident() -> String {
+    get_from_cache(CacheSection::Ident, "ident")
+}
+
 /// Initializes the configuration file.
 /// This function creates a default configuration file if it does not exist, and loads it into the cache.
 pub fn init_config() -> io::Result<()> {
     let config_path = get_config_path()?;
-    let config: ConfigurationFile;
+    let mut config: ConfigurationFile;
 
     if config_path.exists() {
         let mut contents = String::new();
         File::open(&config_path)?.read_to_string(&mut contents)?;
 
-        match serde_yaml::from_str::<ConfigurationFile>(&contents) {
+        match migrate_config(&contents) {
             Ok(mut loaded_config) => {
                 loaded_config.version = env!("CARGO_PKG_VERSION").to_string();
                 config = loaded_config;
             }
-            Err(_) => {
-                log::warn!("Config format mismatch. Attempting migration...");
-                config = migrate_old_config(&contents).unwrap_or_else(|| {
-                    log::error!("Migration failed. Resetting to default config.");
-                    generate_default_config()
-                });
+            Err(e) => {
+                log::error!("Migration failed: {}. Resetting to default config.", e);
+                config = generate_default_config();
             }
         }
     } else {
@@ -552,9 +2472,24 @@ pub fn init_config() -> io::Result<()> {
         config = generate_default_config();
     }
 
-    save_config(&config_path, &config).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    // Make sure the ident is always present and kept in the stable location
+    match config.ident.as_deref() {
+        Some(ident) if !ident.is_empty() => store_ident(ident),
+        _ => config.ident = Some(resolve_ident()),
+    }
+
+    // A managed configuration is used as provisioned, nothing is migrated into the file
+    match config_managed_reason(&config_path, &config) {
+        Some(reason) => log::info!("Configuration is managed ({}), it is not rewritten", reason),
+        None => {
+            migrate_credentials_to_keyring(&mut config);
+
+            save_config(&config_path, &config)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
 
-    log::debug!("config: saved config");
+            log::debug!("config: saved config");
+        }
+    }
 
     /*
         Send data of all cards in events one by one to the front.
@@ -572,43 +2507,180 @@ pub fn init_config() -> io::Result<()> {
     Ok(())
 }
 
-fn migrate_old_config(contents: &str) -> Option<ConfigurationFile> {
-    #[derive(Deserialize)]
-    struct OldConfig {
-        name: String,
-        #[allow(dead_code)]
-        // to say the compiler does not warn about an unused field that is used in another file.
-        version: String,
-        description: String,
-        appearance: Option<AppearanceConfig>,
-        ident: Option<String>,
-        server: Option<ServerConfig>,
-        cards: Option<HashMap<String, String>>, // old cards format
-    }
-
-    let old_config: OldConfig = serde_yaml::from_str(contents).ok()?;
-
-    let mut new_cards = HashMap::new();
-    if let Some(old_cards) = old_config.cards {
-        for (_, card_number) in old_cards {
-            let card_config = CardConfig {
-                iccid: String::new(),
-                expire: None,
-                name: None,
-            };
-            new_cards.insert(card_number, card_config);
+/// Resets the configuration to defaults.
+/// The current file is backed up next to it with a timestamp, the path of the backup is returned.
+/// The ident is preserved unless `force` is set.
+async fn reset_config_to_defaults(
+    config_path: &Path,
+    force: bool,
+) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
+    // Refused before the backup, a managed configuration is not reset
+    ensure_config_mutable(config_path, &load_config(config_path)?)?;
+
+    let backup_path = config_path.with_file_name(format!(
+        "config.backup-{}.yaml",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    fs::copy(config_path, &backup_path)?;
+    log::info!("Configuration is backed up to {:?}", backup_path);
+
+    // Cards of the old configuration, to notify the frontend they are gone
+    let old_cards: Vec<String> = load_config(config_path)
+        .map(|config| config.cards.into_keys().collect())
+        .unwrap_or_default();
+    let old_ident = get_from_cache(CacheSection::Ident, "ident");
+
+    let mut config = generate_default_config();
+    if force || old_ident.is_empty() {
+        let ident = generate_ident();
+        store_ident(&ident);
+        config.ident = Some(ident);
+    } else {
+        config.ident = Some(old_ident);
+    }
+
+    save_config(config_path, &config)?;
+    load_config_to_cache(&config)?;
+
+    // Tear down all connections, they belong to the old configuration. The app connection
+    // is started again with the ident of the new configuration
+    remove_connections_all().await;
+    async_runtime::spawn(app_connection());
+
+    for card_number in old_cards {
+        emit_card_config_event("global-card-config-updated", card_number, None);
+    }
+
+    if let Some(app_handle) = get_app_handle() {
+        if let Err(e) = emit_global_config_server(&app_handle) {
+            log::error!("Failed to emit global config server: {}", e);
         }
     }
 
-    Some(ConfigurationFile {
-        name: old_config.name,
-        version: env!("CARGO_PKG_VERSION").to_string(),
-        description: old_config.description,
-        appearance: old_config.appearance,
-        ident: old_config.ident,
-        server: old_config.server,
-        cards: new_cards,
-    })
+    Ok(backup_path)
+}
+
+/// Public function to reset the configuration to defaults.
+/// This function is a Tauri command that returns the path of the backup of the previous configuration.
+#[tauri::command]
+pub async fn reset_config(force: bool) -> Result<String, String> {
+    let config_path = get_config_path().map_err(|e| {
+        log::error!("Failed to get config path: {}", e);
+        format!("Failed to get config path: {}", e)
+    })?;
+
+    let backup_path = reset_config_to_defaults(&config_path, force)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to reset configuration: {}", e);
+            format!("Failed to reset configuration: {}", e)
+        })?;
+
+    log::info!("Configuration is reset to defaults. Force: {}", force);
+
+    Ok(backup_path.to_string_lossy().into_owned())
+}
+
+/// Current version of the configuration format.
+/// Every format change increments it and adds a step to `migrate_config`.
+const CONFIG_SCHEMA_VERSION: u32 = 2;
+
+/// Parses the configuration and migrates it step by step from the stored schema version
+/// up to `CONFIG_SCHEMA_VERSION`. Configurations without the version are treated as v0.
+fn migrate_config(
+    contents: &str,
+) -> Result<ConfigurationFile, Box<dyn std::error::Error + Send + Sync>> {
+    let mut value: serde_yaml::Value = serde_yaml::from_str(contents)?;
+
+    let stored_version = value
+        .get("schema_version")
+        .and_then(serde_yaml::Value::as_u64)
+        .unwrap_or(0) as u32;
+
+    if stored_version > CONFIG_SCHEMA_VERSION {
+        log::warn!(
+            "Config schema version {} is newer than supported {}. Loading as is.",
+            stored_version,
+            CONFIG_SCHEMA_VERSION
+        );
+    }
+
+    for version in stored_version..CONFIG_SCHEMA_VERSION {
+        log::info!("Migrating config from v{} to v{}", version, version + 1);
+
+        value = match version {
+            0 => migrate_v0_to_v1(value)?,
+            1 => migrate_v1_to_v2(value)?,
+            _ => return Err(format!("No migration from config v{}", version).into()),
+        };
+    }
+
+    let mut config: ConfigurationFile = serde_yaml::from_value(value)?;
+    config.schema_version = config.schema_version.max(CONFIG_SCHEMA_VERSION);
+
+    Ok(config)
+}
+
+/// v0 → v1: the old cards format (HashMap<String, String>, ATR => card number) is converted
+/// to the card number => CardConfig map. Cards already in the new format are kept.
+fn migrate_v0_to_v1(
+    mut value: serde_yaml::Value,
+) -> Result<serde_yaml::Value, Box<dyn std::error::Error + Send + Sync>> {
+    let root = value
+        .as_mapping_mut()
+        .ok_or("Config root is not a mapping")?;
+
+    let mut new_cards = serde_yaml::Mapping::new();
+    if let Some(serde_yaml::Value::Mapping(old_cards)) = root.remove("cards") {
+        for (key, card) in old_cards {
+            match card {
+                // old cards format, the value is the card number
+                serde_yaml::Value::String(card_number) => {
+                    let card_config = CardConfig {
+                        iccid: String::new(),
+                        expire: None,
+                        name: None,
+                        enabled: None,
+                        reader: None,
+                        topic_prefix: None,
+                        qos: None,
+                    };
+                    new_cards.insert(card_number.into(), serde_yaml::to_value(card_config)?);
+                }
+                card => {
+                    new_cards.insert(key, card);
+                }
+            }
+        }
+    }
+
+    root.insert("cards".into(), serde_yaml::Value::Mapping(new_cards));
+    root.insert("schema_version".into(), serde_yaml::Value::from(1u32));
+
+    Ok(value)
+}
+
+/// v1 → v2: every card gets the explicit `enabled: true`.
+fn migrate_v1_to_v2(
+    mut value: serde_yaml::Value,
+) -> Result<serde_yaml::Value, Box<dyn std::error::Error + Send + Sync>> {
+    let root = value
+        .as_mapping_mut()
+        .ok_or("Config root is not a mapping")?;
+
+    if let Some(serde_yaml::Value::Mapping(cards)) = root.get_mut("cards") {
+        for (_, card) in cards.iter_mut() {
+            if let serde_yaml::Value::Mapping(card) = card {
+                if card.get("enabled").map_or(true, serde_yaml::Value::is_null) {
+                    card.insert("enabled".into(), true.into());
+                }
+            }
+        }
+    }
+
+    root.insert("schema_version".into(), serde_yaml::Value::from(2u32));
+
+    Ok(value)
 }
 
 // Default structure config
@@ -620,9 +2692,18 @@ fn generate_default_config() -> ConfigurationFile {
         appearance: Some(AppearanceConfig {
             dark_theme: DarkTheme::Auto,
         }),
-        ident: Some(generate_ident()),
+        ident: Some(resolve_ident()),
         server: None,
         cards: HashMap::new(),
+        smart_card: None,
+        allowed_cards: None,
+        min_version: None,
+        reserved_idents: None,
+        ident_duplicates: IdentDuplicatePolicy::default(),
+        log_levels: None,
+        config_check_interval_secs: None,
+        read_only: false,
+        schema_version: CONFIG_SCHEMA_VERSION,
     }
 }
 
@@ -639,6 +2720,10 @@ pub fn emit_global_config_server(app: &tauri::AppHandle) -> Result<(), Box<dyn E
     config_app_payload.insert("host", host);
     config_app_payload.insert("ident", ident);
     config_app_payload.insert("dark_theme", appearance);
+    config_app_payload.insert(
+        "maintenance",
+        get_smart_card_config().maintenance.to_string(),
+    );
 
     // Emit this data as a global event to update fornt-end fields
     if let Err(e) = app.emit("global-config-server", config_app_payload) {
@@ -647,3 +2732,125 @@ pub fn emit_global_config_server(app: &tauri::AppHandle) -> Result<(), Box<dyn E
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes the configuration to a file of its own in the temp directory.
+    fn temp_config(name: &str, yaml: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "tacho-config-test-{}-{}.yaml",
+            name,
+            std::process::id()
+        ));
+        std::fs::write(&path, yaml).unwrap();
+        path
+    }
+
+    fn card(iccid: &str) -> CardConfig {
+        CardConfig {
+            iccid: iccid.to_string(),
+            expire: None,
+            name: None,
+            enabled: None,
+            reader: None,
+            topic_prefix: None,
+            qos: None,
+        }
+    }
+
+    #[test]
+    fn iccid_owner_is_another_card_number() {
+        let cards = HashMap::from([
+            ("100".to_string(), card("8931000000000000001")),
+            ("200".to_string(), card("")),
+        ]);
+
+        assert_eq!(
+            find_iccid_owner(&cards, "300", "8931000000000000001"),
+            Some("100")
+        );
+        // The card keeps its own ICCID, an unknown ICCID is not checked
+        assert_eq!(find_iccid_owner(&cards, "100", "8931000000000000001"), None);
+        assert_eq!(find_iccid_owner(&cards, "300", ""), None);
+    }
+
+    #[test]
+    fn two_cards_sharing_an_iccid_are_reported() {
+        let yaml = "\
+name: test
+version: 0.0.0
+description: test
+cards:
+  '100':
+    iccid: '8931000000000000001'
+    expire: null
+    name: null
+";
+        let path = temp_config("iccid-conflict", yaml);
+
+        let result = update_card_config(&path, "200", card("8931000000000000001"));
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let error = result.unwrap_err().to_string();
+        assert!(
+            error.contains("already assigned to the card 100"),
+            "{}",
+            error
+        );
+        assert_eq!(written, yaml, "The configuration must not be changed");
+    }
+
+    #[test]
+    fn managed_config_is_refused_before_the_change() {
+        let yaml = "\
+name: test
+version: 0.0.0
+description: test
+ident: machine-1
+read_only: true
+server:
+  host: mqtt://broker:1883
+cards: {}
+";
+        let path = temp_config("managed", yaml);
+
+        let server =
+            update_server_config(&path, "mqtt://other:1883", "machine-2", "Auto", None, None);
+        let ident = set_ident_config(&path, "machine-2");
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        for result in [server.map(|_| ()), ident.map(|_| ())] {
+            let error = result.unwrap_err();
+            assert!(error.is::<ConfigManaged>(), "{}", error);
+        }
+        assert_eq!(written, yaml, "The configuration must not be changed");
+    }
+
+    #[test]
+    fn topic_prefix_defaults_to_the_channel_of_the_server() {
+        let mut server = ServerConfig::default();
+        assert_eq!(server.topic_prefix(), "tacho-bridge");
+
+        server.topic_prefix = Some("fleet-a".to_string());
+        assert_eq!(server.topic_prefix(), "fleet-a");
+    }
+
+    #[test]
+    fn server_debug_hides_the_credentials() {
+        let server = ServerConfig {
+            host: "mqtt://broker:1883".to_string(),
+            username: Some("fleet-user".to_string()),
+            password: Some("fleet-secret".to_string()),
+            ..Default::default()
+        };
+
+        let debug = format!("{:?}", server);
+        assert!(debug.contains("mqtt://broker:1883"), "{}", debug);
+        assert!(!debug.contains("fleet-user"), "{}", debug);
+        assert!(!debug.contains("fleet-secret"), "{}", debug);
+    }
+}
@@ -0,0 +1,59 @@
+//! Module for the secure storage of the broker credentials.
+//!
+//! The credentials are stored in the OS keyring (Keychain, Credential Manager, Secret Service)
+//! keyed by the app ident. Only a marker is left in the config.yaml. If the keyring is not
+//! available, the caller falls back to the plaintext configuration.
+
+// ───── Std Lib ─────
+use std::error::Error;
+
+// ───── External Crates ─────
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+
+/// Service name of the keyring entries.
+const KEYRING_SERVICE: &str = "tacho-card-client";
+
+/// Credentials of the MQTT broker.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// Stores the credentials in the keyring under the given ident.
+pub fn store_in_keyring(
+    ident: &str,
+    credentials: &Credentials,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let secret = serde_json::to_string(credentials)?;
+    Entry::new(KEYRING_SERVICE, ident)?.set_password(&secret)?;
+    Ok(())
+}
+
+/// Reads the credentials stored under the given ident.
+pub fn load_from_keyring(ident: &str) -> Option<Credentials> {
+    let secret = match Entry::new(KEYRING_SERVICE, ident).and_then(|entry| entry.get_password()) {
+        Ok(secret) => secret,
+        Err(e) => {
+            log::error!("Failed to read the credentials from the keyring: {}", e);
+            return None;
+        }
+    };
+
+    match serde_json::from_str(&secret) {
+        Ok(credentials) => Some(credentials),
+        Err(e) => {
+            log::error!("Failed to parse the credentials from the keyring: {}", e);
+            None
+        }
+    }
+}
+
+/// Removes the credentials stored under the given ident.
+pub fn delete_from_keyring(ident: &str) {
+    match Entry::new(KEYRING_SERVICE, ident).and_then(|entry| entry.delete_credential()) {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => log::warn!("Failed to remove the credentials from the keyring: {}", e),
+    }
+}
@@ -0,0 +1,259 @@
+//! Module for the self-test of the application.
+//!
+//! This module provides a one-click health check that sequentially exercises the PC/SC
+//! subsystem, the inserted cards, the configuration and the connection to the broker,
+//! and the diagnostics bundle the users send to the support.
+
+// ───── Std Lib ─────
+use std::ffi::CString;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+// ───── External Crates ─────
+use pcsc::{ReaderState, State as PcscState};
+use serde::Serialize;
+use tokio::net::TcpStream;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+// ───── Local Modules ─────
+use crate::config::{get_from_cache, redacted_config_yaml, split_host_to_parts, CacheSection};
+use crate::global_app_handle::card_event_history;
+use crate::logger::{build_info, get_log_path, system_info};
+use crate::smart_card::debug_task_pool;
+use crate::smart_card::{
+    establish_context, get_managed_card, parse_atr_and_get_protocol, ManagedCard, ReaderOrCard,
+};
+
+/// Timeout for the TCP connection to the broker during the self-test.
+const BROKER_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Number of the last log lines included in the diagnostics bundle.
+const BUNDLE_LOG_LINES: usize = 2000;
+
+/// Result of a single diagnostic check.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticCheck {
+    pub name: String,    // Short name of the check (e.g. "pcsc_context").
+    pub passed: bool,    // Whether the check succeeded.
+    pub message: String, // Human readable details for the support.
+}
+
+/// Structured report returned to the frontend by `run_diagnostics`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsReport {
+    pub passed: bool,                 // True only if all checks passed.
+    pub checks: Vec<DiagnosticCheck>, // Per-check results in the order of execution.
+}
+
+impl DiagnosticsReport {
+    fn push(&mut self, name: &str, passed: bool, message: String) {
+        if passed {
+            log::info!("Diagnostics: {} passed. {}", name, message);
+        } else {
+            log::warn!("Diagnostics: {} failed. {}", name, message);
+        }
+
+        self.passed &= passed;
+        self.checks.push(DiagnosticCheck {
+            name: name.to_string(),
+            passed,
+            message,
+        });
+    }
+}
+
+/// Runs the self-test of the reader, card, configuration and broker connection.
+#[tauri::command]
+pub async fn run_diagnostics() -> DiagnosticsReport {
+    log::info!("Diagnostics are started");
+
+    let mut report = DiagnosticsReport {
+        passed: true,
+        checks: Vec::new(),
+    };
+
+    // Reader name and ATR of every reader holding a card.
+    let mut inserted_cards: Vec<(CString, String)> = Vec::new();
+
+    // ───── PC/SC context ─────
+    match establish_context() {
+        Ok(ctx) => {
+            report.push("pcsc_context", true, "PC/SC context established".into());
+
+            // ───── Readers ─────
+            let mut readers_buf = [0; 2048];
+            match ctx.list_readers(&mut readers_buf) {
+                Ok(names) => {
+                    let mut reader_states: Vec<ReaderState> = names
+                        .map(|name| ReaderState::new(name, PcscState::UNAWARE))
+                        .collect();
+
+                    if reader_states.is_empty() {
+                        report.push("readers", false, "No readers found".into());
+                    } else {
+                        report.push(
+                            "readers",
+                            true,
+                            format!("{} reader(s) found", reader_states.len()),
+                        );
+
+                        if let Err(e) =
+                            ctx.get_status_change(Some(Duration::ZERO), &mut reader_states)
+                        {
+                            log::warn!("Diagnostics: get_status_change failed: {:?}", e);
+                        }
+
+                        for rs in &reader_states {
+                            if rs.event_state().contains(PcscState::PRESENT) {
+                                let atr = hex::encode(rs.atr());
+                                inserted_cards.push((rs.name().to_owned(), atr));
+                            }
+                        }
+                    }
+                }
+                Err(e) => report.push("readers", false, format!("Failed to list readers: {}", e)),
+            }
+        }
+        Err(e) => report.push(
+            "pcsc_context",
+            false,
+            format!("Failed to establish PC/SC context: {}", e),
+        ),
+    }
+
+    // ───── Cards ─────
+    if inserted_cards.is_empty() {
+        report.push("cards", true, "No card inserted, skipped".into());
+    }
+
+    for (reader_name, atr) in inserted_cards {
+        let reader = reader_name.to_string_lossy().into_owned();
+        let protocol = parse_atr_and_get_protocol(&atr);
+
+        // A card of a running task is read through its handle, after the exchange in progress
+        let result = match get_managed_card(ReaderOrCard::Reader(reader.clone())).await {
+            Some(managed_card) => match managed_card.wait_exchange().await {
+                Ok(_exchange) => managed_card.get_iccid().await,
+                Err(e) => Err(e.into()),
+            },
+            // The card may be connected by the monitor at this time, so it is not reset
+            None => match ManagedCard::new(&reader_name, protocol).await {
+                Ok(managed_card) => {
+                    let iccid = managed_card.get_iccid().await;
+                    managed_card.leave();
+                    iccid
+                }
+                Err(e) => Err(e),
+            },
+        };
+
+        match result {
+            Ok(iccid) => report.push("card", true, format!("{}: ICCID {}", reader, iccid)),
+            Err(e) => report.push(
+                "card",
+                false,
+                format!("{}: failed to read ICCID: {}", reader, e),
+            ),
+        }
+    }
+
+    // ───── Server configuration ─────
+    let full_host = get_from_cache(CacheSection::Server, "host");
+    match split_host_to_parts(&full_host) {
+        Ok((host, port)) => {
+            report.push("server_config", true, format!("Server: {}:{}", host, port));
+
+            // ───── Broker connection ─────
+            match tokio::time::timeout(
+                BROKER_CONNECT_TIMEOUT,
+                TcpStream::connect((host.as_str(), port)),
+            )
+            .await
+            {
+                Ok(Ok(_)) => report.push("broker", true, format!("Connected to {}:{}", host, port)),
+                Ok(Err(e)) => report.push("broker", false, format!("Connection failed: {}", e)),
+                Err(_) => report.push(
+                    "broker",
+                    false,
+                    format!("Connection timeout after {:?}", BROKER_CONNECT_TIMEOUT),
+                ),
+            }
+        }
+        Err(e) => {
+            report.push("server_config", false, format!("'{}': {}", full_host, e));
+            report.push("broker", false, "Skipped, server is not configured".into());
+        }
+    }
+
+    log::info!("Diagnostics are finished. Passed: {}", report.passed);
+
+    report
+}
+
+/// Writes the diagnostics bundle to the temp folder and returns its path: the log tail, the
+/// config without the credentials, build and system information, the task pool and the recent
+/// card events. A part that can't be collected contains the reason instead.
+#[tauri::command]
+pub async fn create_diagnostics_bundle() -> Result<String, String> {
+    log::info!("Diagnostics bundle is requested");
+
+    let task_pool = debug_task_pool().await;
+    let files = [
+        ("log_tail.txt", log_tail()),
+        (
+            "config.yaml",
+            redacted_config_yaml().unwrap_or_else(|e| format!("Failed to read config: {}", e)),
+        ),
+        ("build_info.json", to_json(&build_info())),
+        ("system_info.txt", system_info()),
+        ("task_pool.json", to_json(&task_pool)),
+        ("card_events.json", to_json(&card_event_history())),
+    ];
+
+    let path = std::env::temp_dir().join(format!(
+        "tacho-diagnostics-{}.zip",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    write_bundle(&path, &files).map_err(|e| {
+        log::error!("Failed to write diagnostics bundle {:?}: {}", path, e);
+        format!("Failed to write diagnostics bundle: {}", e)
+    })?;
+
+    log::info!("Diagnostics bundle is written to {:?}", path);
+
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Returns the last lines of the log.
+fn log_tail() -> String {
+    let contents = match get_log_path().and_then(|path| {
+        std::fs::read(&path).map_err(|e| format!("Failed to read log {:?}: {}", path, e))
+    }) {
+        Ok(contents) => contents,
+        Err(e) => return e,
+    };
+
+    let contents = String::from_utf8_lossy(&contents);
+    let lines: Vec<&str> = contents.lines().collect();
+    lines[lines.len().saturating_sub(BUNDLE_LOG_LINES)..].join("\n")
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|e| format!("Failed to serialize: {}", e))
+}
+
+fn write_bundle(path: &Path, files: &[(&str, String)]) -> zip::result::ZipResult<()> {
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    for (name, contents) in files {
+        zip.start_file(*name, options)?;
+        zip.write_all(contents.as_bytes())?;
+    }
+
+    zip.finish()?;
+    Ok(())
+}
@@ -0,0 +1,86 @@
+//! Module for the advance warning about expiring cards.
+//!
+//! The `expire` date of every configured card is checked at startup and then periodically.
+//! Cards expiring within the configured window are reported to the frontend once per session,
+//! so the dispatchers have time to order a replacement company card.
+
+// ───── Std Lib ─────
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// ───── External Crates ─────
+use lazy_static::lazy_static;
+
+// ───── Local Modules ─────
+use crate::config::{get_cards_from_cache, get_smart_card_config};
+use crate::global_app_handle::{emit_notification_event, NotificationPayload};
+
+/// Interval between the expiry checks.
+const EXPIRE_CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+lazy_static! {
+    /// Cards already reported in this session, keyed by card number and expire date,
+    /// so a changed expire date is reported again.
+    static ref NOTIFIED_CARDS: Mutex<HashSet<(String, u64)>> = Mutex::new(HashSet::new());
+}
+
+/// Checks the expire date of the cards forever, see `check_cards_expiry`.
+pub async fn expiry_monitor() -> ! {
+    loop {
+        check_cards_expiry();
+        tokio::time::sleep(EXPIRE_CHECK_INTERVAL).await;
+    }
+}
+
+/// Reports the cards that are expired or expire within the warning window.
+/// The expire date is a Unix timestamp in seconds.
+pub fn check_cards_expiry() {
+    let warning_days = get_smart_card_config().expire_warning_days();
+    if warning_days == 0 {
+        return;
+    }
+
+    let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(now) => now.as_secs(),
+        Err(e) => {
+            log::error!("Failed to get the current time: {}", e);
+            return;
+        }
+    };
+    let warning_until = now.saturating_add(warning_days * SECONDS_PER_DAY);
+
+    let mut notified = NOTIFIED_CARDS.lock().unwrap();
+
+    for (card_number, card_config) in get_cards_from_cache() {
+        let Some(expire) = card_config.expire.filter(|expire| *expire > 0) else {
+            continue;
+        };
+
+        if expire > warning_until || !notified.insert((card_number.clone(), expire)) {
+            continue;
+        }
+
+        let card_name = match card_config.name.as_deref() {
+            Some(name) if !name.is_empty() => format!("{} ({})", name, card_number),
+            _ => card_number.clone(),
+        };
+
+        let message = if expire <= now {
+            format!("Card {} has expired", card_name)
+        } else {
+            let days_left = (expire - now).div_ceil(SECONDS_PER_DAY);
+            format!("Card {} expires in {} day(s)", card_name, days_left)
+        };
+
+        log::warn!("{}", message);
+
+        let payload = NotificationPayload {
+            notification_type: "expire".to_string(),
+            message,
+        };
+        emit_notification_event("global-notification", payload);
+    }
+}
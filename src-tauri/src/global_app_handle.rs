@@ -1,4 +1,6 @@
 // ───── Std Lib ─────
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::Mutex;
 
 // ───── External Crates ─────
@@ -7,18 +9,47 @@ use serde::Serialize;
 use tauri::{AppHandle, Emitter};
 
 // ───── Local Modules ─────
+use crate::config::get_smart_card_config;
 use crate::config::CardConfig;
+use crate::smart_card::{CardGeneration, CardLastError, PinTries};
+
+/// Events kept while the app handle is not set, the oldest are dropped above this limit.
+const MAX_PENDING_EVENTS: usize = 500;
+
+/// Message to the event dispatcher thread.
+enum DispatchMessage {
+    Event(String, serde_json::Value), // Event name and its serialized payload.
+    Flush,                            // The app handle is set, the pending events can be sent.
+}
 
 // Global application handle used for emitting events from anywhere.
 // Wrapped in a Mutex to ensure safe concurrent access.
 lazy_static! {
     static ref APP_HANDLE: Mutex<Option<AppHandle>> = Mutex::new(None);
+
+    /// Last card state events, oldest first, see `card_event_history`.
+    static ref CARD_EVENT_HISTORY: Mutex<VecDeque<CardEventRecord>> = Mutex::new(VecDeque::new());
+
+    /// Sender to the event dispatcher thread. The events are emitted on that thread, so
+    /// a busy webview never blocks the caller, e.g. the smart card loop.
+    static ref DISPATCHER: Mutex<Sender<DispatchMessage>> = {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("event-dispatcher".to_string())
+            .spawn(move || run_dispatcher(receiver))
+            .expect("Failed to start the event dispatcher thread");
+        Mutex::new(sender)
+    };
 }
 
 // initialize the global app handle
 pub fn set_app_handle(handle: AppHandle) {
     let mut app_handle = APP_HANDLE.lock().unwrap();
     *app_handle = Some(handle);
+    drop(app_handle);
+
+    // Deliver the events emitted before the handle was set
+    send_to_dispatcher(DispatchMessage::Flush);
 }
 
 // getting the global app handle
@@ -27,6 +58,51 @@ pub fn get_app_handle() -> Option<AppHandle> {
     app_handle.clone()
 }
 
+/// Queues the event for the dispatcher thread. Returns right away.
+fn dispatch<S: Serialize>(event_name: &str, payload: S) {
+    match serde_json::to_value(payload) {
+        Ok(value) => send_to_dispatcher(DispatchMessage::Event(event_name.to_string(), value)),
+        Err(e) => log::error!("Failed to serialize {}: {}", event_name, e),
+    }
+}
+
+fn send_to_dispatcher(message: DispatchMessage) {
+    if DISPATCHER.lock().unwrap().send(message).is_err() {
+        log::error!("Event dispatcher is stopped. The event is dropped.");
+    }
+}
+
+/// Emits the queued events in order. The events are kept until the app handle is set.
+fn run_dispatcher(receiver: Receiver<DispatchMessage>) {
+    let mut pending: VecDeque<(String, serde_json::Value)> = VecDeque::new();
+
+    for message in receiver {
+        if let DispatchMessage::Event(event_name, payload) = message {
+            if pending.len() == MAX_PENDING_EVENTS {
+                if let Some((dropped, _)) = pending.pop_front() {
+                    log::warn!("Too many pending events. {} is dropped.", dropped);
+                }
+            }
+            pending.push_back((event_name, payload));
+        }
+
+        let Some(app_handle) = get_app_handle() else {
+            log::debug!(
+                "App handle is not set. {} event(s) are pending.",
+                pending.len()
+            );
+            continue;
+        };
+
+        while let Some((event_name, payload)) = pending.pop_front() {
+            match app_handle.emit(&event_name, payload) {
+                Ok(()) => log::debug!("{} has been sent", event_name),
+                Err(e) => log::error!("Error emitting {}: {:?}", event_name, e),
+            }
+        }
+    }
+}
+
 /// Represents the state of a tachograph card.
 ///
 /// This structure holds information about a tachograph card currently being
@@ -34,11 +110,15 @@ pub fn get_app_handle() -> Option<AppHandle> {
 ///
 /// # Fields
 ///
-/// * `atr` - A string representing the Answer To Reset (ATR) of the card. The ATR is a sequence
-///   of bytes returned by the card upon reset, identifying the card's communication parameters.
+/// * `iccid` - The ICCID of the card, read from EF ICC.
 /// * `reader_name` - The name of the smart card reader through which the card is being accessed.
 /// * `card_state` - A string describing the current state of the card (e.g., "Inserted", "Removed").
 /// * `card_number` - The identification number of the tachograph card.
+/// * `generation` - The generation of the tachograph card, if it has been detected.
+/// * `atr` - A string representing the Answer To Reset (ATR) of the card, when a card is present.
+///   The ATR is a sequence of bytes returned by the card upon reset, identifying the card's communication parameters.
+/// * `protocol` - The communication protocol used with the card ("T0" or "T1"), when a card is present.
+/// * `errored` - The card is paused by the circuit breaker after repeated APDU failures.
 #[derive(Clone, serde::Serialize)]
 pub struct TachoState {
     pub iccid: String,
@@ -47,36 +127,63 @@ pub struct TachoState {
     pub card_number: String,
     pub online: Option<bool>,
     pub authentication: Option<bool>,
+    pub generation: Option<CardGeneration>,
+    pub atr: Option<String>,
+    pub protocol: Option<String>,
+    pub errored: Option<bool>,
 }
 
-pub fn emit_event(
-    event_name: &str,
-    iccid: String,
-    reader_name: String,
-    card_state: String,
-    card_number: String,
-    online: Option<bool>,
-    authentication: Option<bool>,
-) {
-    let payload = TachoState {
-        iccid,
-        reader_name,
-        card_state,
-        card_number,
-        online,
-        authentication,
-    };
+impl TachoState {
+    /// Returns a copy of the state with the given connection and authentication status.
+    pub fn with_status(&self, online: Option<bool>, authentication: Option<bool>) -> Self {
+        Self {
+            online,
+            authentication,
+            ..self.clone()
+        }
+    }
 
-    if let Some(app_handle) = get_app_handle() {
-        if let Err(e) = app_handle.emit(event_name, payload) {
-            println!("Error: {:?}", e);
+    /// Returns a copy of the state with the given circuit breaker status.
+    pub fn with_errored(self, errored: bool) -> Self {
+        Self {
+            errored: Some(errored),
+            ..self
         }
-        println!("{} has been sent", event_name);
-    } else {
-        println!("App card handle is not set");
     }
 }
 
+/// Card state event kept in the history, with the local time it was emitted at.
+#[derive(Clone, serde::Serialize)]
+pub struct CardEventRecord {
+    pub timestamp: String, // RFC 3339, local time.
+    pub event: TachoState,
+}
+
+pub fn emit_event(event_name: &str, payload: TachoState) {
+    record_card_event(&payload);
+    dispatch(event_name, payload);
+}
+
+/// Appends the state to the history, the oldest records are dropped above the configured size.
+fn record_card_event(state: &TachoState) {
+    let limit = get_smart_card_config().event_history_size();
+    let mut history = CARD_EVENT_HISTORY.lock().unwrap();
+
+    history.push_back(CardEventRecord {
+        timestamp: chrono::Local::now().to_rfc3339(),
+        event: state.clone(),
+    });
+    while history.len() > limit {
+        history.pop_front();
+    }
+}
+
+/// Returns the recent card state transitions, oldest first, for the "recent activity" panel.
+#[tauri::command]
+pub fn card_event_history() -> Vec<CardEventRecord> {
+    CARD_EVENT_HISTORY.lock().unwrap().iter().cloned().collect()
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct CardConfigPayload {
     pub card_number: String,
@@ -89,15 +196,7 @@ pub fn emit_card_config_event(event_name: &str, card_number: String, config: Opt
         content: config,
     };
 
-    if let Some(app_handle) = get_app_handle() {
-        if let Err(e) = app_handle.emit(event_name, payload) {
-            println!("Error emitting {}: {:?}", event_name, e);
-        } else {
-            println!("{} has been sent", event_name);
-        }
-    } else {
-        println!("App card handle is not set");
-    }
+    dispatch(event_name, payload);
 }
 
 #[derive(Clone, Serialize)]
@@ -107,12 +206,149 @@ pub struct NotificationPayload {
 }
 
 pub fn emit_notification_event(event_name: &str, payload: NotificationPayload) {
-    if let Some(app_handle) = get_app_handle() {
-        if let Err(e) = app_handle.emit(event_name, payload) {
-            println!("Error: {:?}", e);
-        }
-        println!("{} has been sent", event_name);
-    } else {
-        println!("App notification handle is not set");
-    }
+    dispatch(event_name, payload);
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CardErrorPayload {
+    pub card_number: String,
+    pub last_error: Option<CardLastError>, // None when the error is cleared.
+}
+
+pub fn emit_card_error_event(
+    event_name: &str,
+    card_number: String,
+    last_error: Option<CardLastError>,
+) {
+    dispatch(
+        event_name,
+        CardErrorPayload {
+            card_number,
+            last_error,
+        },
+    );
+}
+
+/// Card inserted into a reader without a known card number.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnregisteredCardPayload {
+    pub iccid: String,
+    pub reader_name: String,
+    pub atr: String,
+}
+
+pub fn emit_unregistered_card_event(event_name: &str, payload: UnregisteredCardPayload) {
+    dispatch(event_name, payload);
+}
+
+/// Category of a broker connection error, so the UI can tell the user what to check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BrokerErrorKind {
+    Unreachable,      // The server can't be reached, e.g. a wrong address or no network.
+    Refused,          // The server refused the connection, e.g. a closed port or bad credentials.
+    Timeout,          // The server or the network did not answer in time.
+    ServerDisconnect, // The server closed the connection, e.g. the channel is turned off.
+    Tls,              // The TLS handshake failed.
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokerErrorPayload {
+    pub client_id: String, // Card number, or the ident for the app connection.
+    pub kind: BrokerErrorKind,
+    pub message: String, // Error details as logged.
+}
+
+pub fn emit_broker_error_event(event_name: &str, payload: BrokerErrorPayload) {
+    dispatch(event_name, payload);
+}
+
+#[derive(Clone, Serialize)]
+pub struct MaintenancePayload {
+    pub enabled: bool,
+}
+
+pub fn emit_maintenance_event(event_name: &str, enabled: bool) {
+    dispatch(event_name, MaintenancePayload { enabled });
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReaderChangedPayload {
+    pub card_number: String,
+    pub old_reader: String,
+    pub new_reader: String,
+}
+
+pub fn emit_reader_changed_event(
+    event_name: &str,
+    card_number: String,
+    old_reader: String,
+    new_reader: String,
+) {
+    let payload = ReaderChangedPayload {
+        card_number,
+        old_reader,
+        new_reader,
+    };
+
+    dispatch(event_name, payload);
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateAvailablePayload {
+    pub current_version: String,
+    pub latest_version: String,
+    pub download_url: String,
+    pub mandatory: bool, // The current version is below the configured minimum version.
+}
+
+pub fn emit_update_available_event(event_name: &str, payload: UpdateAvailablePayload) {
+    dispatch(event_name, payload);
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PinTriesPayload {
+    pub card_number: String,
+    pub pin_tries: PinTries,
+}
+
+pub fn emit_pin_tries_event(event_name: &str, card_number: String, pin_tries: PinTries) {
+    dispatch(
+        event_name,
+        PinTriesPayload {
+            card_number,
+            pin_tries,
+        },
+    );
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CardPausedPayload {
+    pub card_number: String,
+    pub paused: bool,
+}
+
+pub fn emit_card_paused_event(event_name: &str, card_number: String, paused: bool) {
+    dispatch(
+        event_name,
+        CardPausedPayload {
+            card_number,
+            paused,
+        },
+    );
+}
+
+/// APDU of the server exchange for the protocol console of the UI.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApduPayload {
+    pub card_number: String,
+    pub direction: String, // "C-APDU" for the command, "R-APDU" for the response.
+    pub hex: String,       // The APDU, only the header or the status word when redacted.
+    pub status_word: Option<String>, // Status word of a response.
+    pub elapsed_ms: Option<u64>, // Time from the command to the response.
+}
+
+pub fn emit_apdu_event(event_name: &str, payload: ApduPayload) {
+    dispatch(event_name, payload);
 }
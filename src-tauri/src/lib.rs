@@ -2,6 +2,9 @@
 // ───── Modules ─────
 mod app_connect; // Application connection to the MQTT broker.
 mod config; // Configuration handling.
+mod credentials; // Secure storage of the broker credentials.
+mod diagnostics; // Self-test of the reader, card and broker.
+mod expiry; // Advance warning about expiring cards.
 mod global_app_handle;
 mod logger; // Logging functionality.
 mod mqtt; // MQTT communication.
@@ -13,9 +16,34 @@ use tauri::{async_runtime, Listener, Manager, WindowEvent}; // Tauri application
 
 static LOGGER_INIT: Once = Once::new();
 
+/// Environment variable allowing several instances, e.g. for multi-reader-per-machine setups.
+const ALLOW_MULTIPLE_INSTANCES_ENV: &str = "TACHO_ALLOW_MULTIPLE_INSTANCES";
+
 pub fn run() {
+    let mut builder = tauri::Builder::default();
+
+    // Two instances would fight over the readers and the MQTT client IDs. A second instance
+    // exits right away and the running one is notified and brought to the front.
+    if std::env::var_os(ALLOW_MULTIPLE_INSTANCES_ENV).is_none() {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            log::warn!("Another instance of the application was started and closed");
+
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.unminimize();
+                let _ = window.set_focus();
+            }
+
+            let payload = global_app_handle::NotificationPayload {
+                notification_type: "instance".to_string(),
+                message: "The application is already running. The second instance is closed."
+                    .to_string(),
+            };
+            global_app_handle::emit_notification_event("global-notification", payload);
+        }));
+    }
+
     // start builder to run tauri applicationrustup target add aarch64-pc-windows-msvc
-    tauri::Builder::default()
+    builder
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_opener::init())
         .setup(move |app| {
@@ -27,7 +55,7 @@ pub fn run() {
 
             if let Some(window) = app.get_webview_window("main") {
                 window
-                    .set_title("TransportKlok: Tachograafkaart Verbinder")
+                    .set_title(&logger::window_title())
                     .expect("Failed to set window title");
 
                 let front_app_handle = app_handle.clone();
@@ -73,6 +101,21 @@ pub fn run() {
                         smart_card::sc_monitor().await;
                     });
 
+                    async_runtime::spawn(async {
+                        // Restart the card monitoring if the monitor loop stalls
+                        smart_card::sc_watchdog().await;
+                    });
+
+                    async_runtime::spawn(async {
+                        // Warn about the cards nearing expiry, at startup and periodically
+                        expiry::expiry_monitor().await;
+                    });
+
+                    async_runtime::spawn(async {
+                        // Compare the config cache with the file, if enabled in the config
+                        config::config_integrity_monitor().await;
+                    });
+
                     async_runtime::spawn(async {
                         // Start Main MQTT App client connection
                         app_connect::app_connection().await;
@@ -90,12 +133,50 @@ pub fn run() {
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
-            config::update_card,           // update list of cards from the frontend
-            config::update_server,         // update server config from the frontend
-            config::remove_card,           // remove card from config
-            smart_card::manual_sync_cards, // manual sync cards from the frontend
-            app_connect::app_connection,   // App connection to the MQTT broker
-            logger::frontend_log,          // Frontend -> Rust log bridge
+            config::update_card,             // update list of cards from the frontend
+            config::update_server,           // update server config from the frontend
+            config::update_mqtt_settings,    // keep-alive, QoS and channel capacity only
+            config::get_config_mutability,   // managed config disables the edit controls
+            config::remove_card,             // remove card from config
+            config::set_card_enabled,        // enable or disable the card without deleting it
+            config::rename_card,             // change only the custom name of the card
+            config::set_card_iccid,          // pair the card number with the ICCID
+            config::set_card_reader,         // pin the card to the preferred reader
+            config::set_card_topic,          // topic prefix and QoS overrides of the card
+            config::get_ident,               // read-only access to the active ident
+            config::set_ident,               // assign a specific ident to the machine
+            config::reload_server_config,    // apply the manually edited server section
+            config::refresh_cache,           // re-read the whole config file into the cache
+            config::reset_config,            // reset config to defaults with a backup
+            smart_card::manual_sync_cards,   // manual sync cards from the frontend
+            smart_card::refresh_iccid,       // force re-read of the card ICCID
+            smart_card::get_card_identity,   // holder of the card read from the card
+            smart_card::benchmark_reader,    // APDU latency of the reader
+            smart_card::reset_card_auth,     // reset the card and its authentication state
+            smart_card::connect_card,        // connect a card stub for the broker tests
+            smart_card::force_remove_reader, // drop the tasks of a reader gone unnoticed
+            smart_card::list_readers,        // readers with vendor and firmware information
+            // reader names for the reader dropdown
+            smart_card::list_readers_for_selection,
+            smart_card::get_pin_tries, // PIN try counter before the card is blocked
+            smart_card::pause_card,    // requests of the card are answered as paused
+            smart_card::resume_card,   // the card processes the requests again
+            smart_card::parse_atr,     // protocols and speed decoded from an ATR
+            smart_card::get_cards_snapshot, // configured cards with their live state
+            smart_card::set_maintenance_mode, // pause all card processing
+            smart_card::debug_task_pool, // current task pool for the support
+            smart_card::connection_metrics, // reconnects and uptime of the card connections
+            // recent card state transitions
+            global_app_handle::card_event_history,
+            mqtt::test_server_connection, // probe the broker before saving the server
+            app_connect::app_connection,  // App connection to the MQTT broker
+            logger::frontend_log,         // Frontend -> Rust log bridge
+            logger::get_build_info,       // Version, commit and build time for the support
+            logger::refresh_window_title, // name, version and the available update
+            logger::get_paths,            // config and log file locations
+            diagnostics::run_diagnostics, // One-click health check for the support
+            // zip of logs and state for the support
+            diagnostics::create_diagnostics_bundle,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
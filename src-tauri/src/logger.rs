@@ -1,9 +1,13 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
 use std::path::PathBuf;
+use std::sync::Mutex;
 // use std::fs;
 // use std::error::Error; // Импортируем трэйт Error
 
 use fern;
 use log;
+use once_cell::sync::OnceCell;
 use reqwest;
 use serde::Deserialize;
 use sys_info;
@@ -11,13 +15,122 @@ use tauri::async_runtime;
 use tauri::Manager;
 // use tauri::Emitter;
 
+use crate::config::get_config_path;
+use crate::config::get_data_dir;
+use crate::config::get_log_levels;
+use crate::config::get_min_version;
 use crate::global_app_handle::get_app_handle;
 use crate::global_app_handle::emit_notification_event;
 use crate::global_app_handle::NotificationPayload;
+use crate::global_app_handle::{emit_update_available_event, UpdateAvailablePayload};
+use crate::smart_card::get_readers_info;
 
 #[derive(Deserialize, Debug)]
 struct Release {
     tag_name: String,
+    html_url: Option<String>, // Release page with the downloads.
+}
+
+/// Exact build of the application, to correlate the logs with the source revision.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BuildInfo {
+    pub version: String,         // CARGO_PKG_VERSION.
+    pub git_hash: String,        // Commit the application is built from, "unknown" without git.
+    pub build_timestamp: String, // Build time in RFC 3339, UTC.
+}
+
+/// Returns the build metadata captured by the build script.
+pub fn build_info() -> BuildInfo {
+    let build_timestamp = env!("TACHO_BUILD_TIMESTAMP")
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .map_or_else(|| "unknown".to_string(), |time| time.to_rfc3339());
+
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_hash: env!("TACHO_GIT_HASH").to_string(),
+        build_timestamp,
+    }
+}
+
+#[tauri::command]
+pub fn get_build_info() -> BuildInfo {
+    build_info()
+}
+
+/// Application name in the window title.
+const APP_TITLE: &str = "TransportKlok: Tachograafkaart Verbinder";
+
+/// Newer version found by the version check, shown in the window title.
+static NEWER_VERSION: OnceCell<String> = OnceCell::new();
+
+/// Returns the window title: the application name and the running version, with the newer
+/// version if the version check found one.
+pub fn window_title() -> String {
+    let title = format!("{} v{}", APP_TITLE, env!("CARGO_PKG_VERSION"));
+    match NEWER_VERSION.get() {
+        Some(newer_version) => format!("{} (update {} available)", title, newer_version),
+        None => title,
+    }
+}
+
+/// Sets the window title again, e.g. after the version check found a newer version.
+#[tauri::command]
+pub fn refresh_window_title() -> Result<(), String> {
+    let window = get_app_handle()
+        .and_then(|app_handle| app_handle.get_webview_window("main"))
+        .ok_or("Main window is not available")?;
+
+    window
+        .set_title(&window_title())
+        .map_err(|e| format!("Failed to set the window title: {}", e))
+}
+
+/// APDU trace file, opened on the first traced APDU. None if it can not be opened.
+static APDU_TRACE_FILE: OnceCell<Option<Mutex<File>>> = OnceCell::new();
+
+/// Resolves the directory of the log files, the same as the one of the config.
+fn get_log_dir() -> Result<PathBuf, String> {
+    get_data_dir().map_err(|e| format!("Failed to resolve log directory: {}", e))
+}
+
+/// Resolves the path of the main log file.
+pub fn get_log_path() -> Result<PathBuf, String> {
+    get_log_dir().map(|dir| dir.join("log.txt"))
+}
+
+/// Locations of the files the user or the support may need to open.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AppPaths {
+    pub config_path: String, // config.yaml
+    pub log_path: String,    // log.txt
+}
+
+#[tauri::command]
+pub fn get_paths() -> Result<AppPaths, String> {
+    let config_path = get_config_path().map_err(|e| {
+        log::error!("Failed to resolve config path: {}", e);
+        format!("Failed to resolve config path: {}", e)
+    })?;
+    let log_path = get_log_path().map_err(|e| {
+        log::error!("{}", e);
+        e
+    })?;
+
+    Ok(AppPaths {
+        config_path: config_path.to_string_lossy().into_owned(),
+        log_path: log_path.to_string_lossy().into_owned(),
+    })
+}
+
+/// Tells the user that the log file can't be written.
+fn notify_log_unavailable(message: &str) {
+    let payload = NotificationPayload {
+        notification_type: "access".to_string(),
+        message: message.to_string(),
+    };
+    emit_notification_event("global-notification", payload);
 }
 
 /// Sets up logging for the application.
@@ -26,44 +139,43 @@ struct Release {
 /// based on the operating system and initializes the logging format and level.
 ///
 pub fn setup_logging() {
-    let Some(app_handle) = get_app_handle() else {
-        eprintln!("Failed to initialize logging: app handle is not set");
-        return;
-    };
-
-    let mut log_path: PathBuf = match app_handle.path().app_data_dir() {
+    let log_path: PathBuf = match get_log_path() {
         Ok(path) => path,
         Err(e) => {
-            eprintln!("Failed to resolve app_data_dir for logs: {}", e);
+            eprintln!("Failed to initialize logging: {}", e);
+            notify_log_unavailable("Log folder is unavailable");
             return;
         }
     };
 
-    if let Err(e) = std::fs::create_dir_all(&log_path) {
-        eprintln!("Failed to create log directory: {}", e);
-        return;
-    }
-
-    log_path.push("log.txt");
-
     match fern::log_file(&log_path) {
         // Check if the log file can be created. Permission check.
         Ok(file) => file,
         Err(e) => {
             eprintln!("Failed to create log file: {}", e);
-            log::warn!("No permission to write log file at: {:?}", log_path);
-
-            let payload = NotificationPayload {
-                notification_type: "access".to_string(),
-                message: "No permission to write log file".to_string(),
-            };
-            emit_notification_event("global-notification", payload);
+            if e.kind() == std::io::ErrorKind::PermissionDenied {
+                log::warn!("No permission to write log file at: {:?}", log_path);
+                notify_log_unavailable("No permission to write log file");
+            } else {
+                log::warn!("Log file path is unavailable: {:?}", log_path);
+                notify_log_unavailable("Log file path is unavailable");
+            }
 
             return;
         }
     };
 
-    let init_log_result = fern::Dispatch::new()
+    // Per module overrides of the global level, e.g. to silence rumqttc
+    let mut dispatch = fern::Dispatch::new();
+    let mut invalid_levels = Vec::new();
+    for (target, level) in get_log_levels() {
+        match level.parse::<log::LevelFilter>() {
+            Ok(level) => dispatch = dispatch.level_for(target, level),
+            Err(_) => invalid_levels.push(format!("{}: {}", target, level)),
+        }
+    }
+
+    let init_log_result = dispatch
         .format(|out, message, record| {
             out.finish(format_args!(
                 "{}[{}][{}] {}",
@@ -87,6 +199,10 @@ pub fn setup_logging() {
 
     // Log the application launch
     log::info!("-== Application is launched ==-");
+    log::info!("Log file: {:?}", log_path);
+    for invalid_level in invalid_levels {
+        log::warn!("Invalid log level is ignored: {}", invalid_level);
+    }
 
     // Check for the latest version asynchronously
     async_runtime::spawn(async {
@@ -95,11 +211,65 @@ pub fn setup_logging() {
         }
     });
 
-    // Log system information
+    // Log the exact build and system information
+    log_build_info();
     log_system_info();
+
+    // Log connected readers
+    log_readers_info();
+}
+
+/// Writes an APDU to the dedicated `apdu.log` next to the main log.
+/// Only called when the `apdu_trace` option is enabled, the file is opened on the first call.
+pub fn trace_apdu(client_id: &str, direction: &str, apdu_hex: &str) {
+    let trace_file = APDU_TRACE_FILE.get_or_init(|| {
+        let path = get_log_dir().map(|dir| dir.join("apdu.log"));
+        match path.and_then(|path| {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .map_err(|e| format!("{:?}: {}", path, e))
+        }) {
+            Ok(file) => Some(Mutex::new(file)),
+            Err(e) => {
+                log::warn!("Failed to open APDU trace file: {}", e);
+                None
+            }
+        }
+    });
+
+    if let Some(file) = trace_file {
+        let mut file = file.lock().unwrap();
+        if let Err(e) = writeln!(
+            file,
+            "{} {} {} {}",
+            chrono::Local::now().format("[%Y-%m-%d][%H:%M:%S%.3f]"),
+            client_id,
+            direction,
+            apdu_hex
+        ) {
+            log::warn!("Failed to write APDU trace: {}", e);
+        }
+    }
+}
+
+fn log_build_info() {
+    let info = build_info();
+    log::info!(
+        "Version: {}, Commit: {}, Built: {}",
+        info.version,
+        info.git_hash,
+        info.build_timestamp
+    );
 }
 
 fn log_system_info() {
+    log::info!("{}", system_info());
+}
+
+/// Describes the OS and the hardware, for the log and the diagnostics bundle.
+pub fn system_info() -> String {
     let os_type = sys_info::os_type().unwrap_or_else(|_| "Unknown".to_string());
     let os_release = sys_info::os_release().unwrap_or_else(|_| "Unknown".to_string());
     let hostname = sys_info::hostname().unwrap_or_else(|_| "Unknown".to_string());
@@ -111,15 +281,28 @@ fn log_system_info() {
         |mem| format!("total {} KB, free {} KB", mem.total, mem.free),
     );
 
-    log::info!(
+    format!(
         "OS Type: {}, OS Release: {}, Hostname: {}, Number of CPUs: {} ({}), Memory: {}",
-        os_type,
-        os_release,
-        hostname,
-        cpu_num,
-        cpu_speed,
-        mem_info
-    );
+        os_type, os_release, hostname, cpu_num, cpu_speed, mem_info
+    )
+}
+
+fn log_readers_info() {
+    match get_readers_info() {
+        Ok(readers) if readers.is_empty() => log::info!("Readers: none connected"),
+        Ok(readers) => {
+            for reader in readers {
+                log::info!(
+                    "Reader: {}, Vendor: {}, Firmware: {}{}",
+                    reader.name,
+                    reader.vendor_name.as_deref().unwrap_or("Unknown"),
+                    reader.firmware_version.as_deref().unwrap_or("Unknown"),
+                    if reader.is_virtual { " (virtual)" } else { "" }
+                );
+            }
+        }
+        Err(e) => log::warn!("Readers: failed to get the information: {}", e),
+    }
 }
 
 async fn check_latest_version() -> Result<(), reqwest::Error> {
@@ -164,6 +347,22 @@ async fn check_latest_version() -> Result<(), reqwest::Error> {
                 .into(),
             };
             emit_notification_event("global-notification", payload);
+
+            // Structured event for the update banner, the notification above is kept for older UIs
+            let mandatory = get_min_version()
+                .is_some_and(|min_version| current_version_num < version_to_number(&min_version));
+            let payload = UpdateAvailablePayload {
+                current_version: current_version.to_string(),
+                latest_version: latest_version.clone(),
+                download_url: release.html_url.clone().unwrap_or_else(|| url.to_string()),
+                mandatory,
+            };
+            emit_update_available_event("global-update-available", payload);
+
+            let _ = NEWER_VERSION.set(latest_version.clone());
+            if let Err(e) = refresh_window_title() {
+                log::warn!("{}", e);
+            }
         } else {
             log::info!(
                 "Version (current: {}, latest: {}). You are using the latest version.",
@@ -5,27 +5,45 @@
 // ───── Std Lib ─────
 use std::ffi::CStr; // For handling C-style strings in Rust.
 use std::io::ErrorKind; // For categorizing I/O errors.
+use std::sync::atomic::{AtomicBool, Ordering}; // Connection status shared with the task pool.
+use std::sync::Arc; // Shared ownership of the connection status.
+use std::sync::Mutex as StdMutex; // Connection statistics shared with the task pool.
 use std::time::Duration; // For specifying time durations.
 
+// ───── Tokio ─────
+use tokio::sync::Notify; // Stop request of the card task.
+
 // ───── MQTT Client Library (rumqttc) ─────
+use rumqttc::v5::mqttbytes::v5::SubscribeReasonCode; // Result of the subscription for every topic filter.
 use rumqttc::v5::mqttbytes::QoS; // Quality of Service levels for MQTT.
 use rumqttc::v5::ConnectionError; // For handling MQTT connection errors.
 use rumqttc::v5::StateError::{self, AwaitPingResp, ServerDisconnect}; // Specific error for server disconnection.
-use rumqttc::v5::{AsyncClient, Event, Incoming, MqttOptions}; // Core MQTT async client and options.
+use rumqttc::v5::{AsyncClient, Event, EventLoop, Incoming, MqttOptions}; // Core MQTT async client and options.
 
 // ───── Tauri ─────
 use tauri::async_runtime::{self, JoinHandle}; // Async runtime and task join handles for Tauri apps.
 
 // ───── Serde (Serialization / Deserialization) ─────
-use serde_json::Value; // For working with JSON data structures.
+use serde::Deserialize; // For the typed requests of the server.
 
 // ───── Local Modules ─────
+use crate::config::get_card_mqtt_settings; // Function to get the topic prefix and the QoS of the card.
 use crate::config::get_from_cache; // Function to get data from cache for syncing server data.
+use crate::config::get_server_config; // Function to get the server settings from cache.
+use crate::config::get_server_credentials; // Function to get the broker credentials.
+use crate::config::get_smart_card_config; // Function to get the card processing settings.
+use crate::config::is_card_allowed; // Function to check the card against the allowed cards.
+use crate::config::is_card_enabled; // Function to check whether the card may be connected.
+use crate::config::is_preferred_reader; // Function to check the card against its preferred reader.
 use crate::config::split_host_to_parts; // Function to split the host into parts for MQTT connection.
 use crate::config::CacheSection; // Enum for cache sections for getting data from cache.
-use crate::global_app_handle::emit_event; // Sends events to the frontend via global app handle.
-use crate::smart_card::ProcessingCard;
-use crate::smart_card::{ManagedCard, TASK_POOL}; // Managed card object and global task pool for MQTT handling.
+use crate::global_app_handle::emit_pin_tries_event; // PIN try counter of the workshop cards.
+use crate::global_app_handle::{emit_broker_error_event, BrokerErrorKind, BrokerErrorPayload}; // Broker errors to the frontend.
+use crate::global_app_handle::{emit_event, TachoState}; // Sends events to the frontend via global app handle.
+use crate::global_app_handle::{emit_notification_event, NotificationPayload}; // Notifications to the frontend.
+use crate::smart_card::{clear_card_error, record_card_error}; // Last error of the card shown in the UI.
+use crate::smart_card::{normalize_apdu_hex, protocol_name, ConnectionMetrics, ProcessingCard};
+use crate::smart_card::{CardInterrupted, ManagedCard, PinTries, TASK_POOL}; // Managed card object and global task pool for MQTT handling.
 
 /// Timeout in seconds to wait before reconnecting to the server.
 ///
@@ -33,20 +51,151 @@ use crate::smart_card::{ManagedCard, TASK_POOL}; // Managed card object and glob
 /// to the MQTT server in case of connection loss.
 const SLEEP_DURATION_SECS: u64 = 10;
 
-// /// Ensures an MQTT connection for the specified client ID.
+/// Time without requests after which the exchange of an unfinished authentication is released
+/// and the card is reset, so the commands are not refused as busy until the next "finish".
+const EXCHANGE_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Answer to the APDUs of a paused card, "Conditions of use not satisfied". See `pause_card`.
+const PAUSED_RESPONSE: &str = "6985";
+
+/// Maps the connection error to the category shown to the user.
+pub fn broker_error_kind(error: &ConnectionError) -> BrokerErrorKind {
+    match error {
+        ConnectionError::Io(io_err) => match io_err.kind() {
+            ErrorKind::ConnectionRefused => BrokerErrorKind::Refused,
+            ErrorKind::TimedOut => BrokerErrorKind::Timeout,
+            _ => BrokerErrorKind::Unreachable,
+        },
+        ConnectionError::ConnectionRefused(_) => BrokerErrorKind::Refused,
+        ConnectionError::Timeout(_) => BrokerErrorKind::Timeout,
+        ConnectionError::Tls(_) => BrokerErrorKind::Tls,
+        ConnectionError::MqttState(ServerDisconnect { .. }) => BrokerErrorKind::ServerDisconnect,
+        ConnectionError::MqttState(AwaitPingResp { .. }) => BrokerErrorKind::Timeout,
+        ConnectionError::MqttState(StateError::Io(_)) => BrokerErrorKind::Unreachable,
+        _ => BrokerErrorKind::Other,
+    }
+}
+
+/// Reports the connection error of the card or the app connection to the frontend.
+pub fn report_broker_error(client_id: &str, error: &ConnectionError) {
+    let payload = BrokerErrorPayload {
+        client_id: client_id.to_string(),
+        kind: broker_error_kind(error),
+        message: error.to_string(),
+    };
+    emit_broker_error_event("global-broker-error", payload);
+}
+
+/// Builds the request topic of the app connection with the topic prefix of the server.
+/// It uses the card scheme with the ident for the management commands.
+pub fn request_topic(ident: &str) -> String {
+    card_request_topic(&get_server_config().topic_prefix(), ident)
+}
+
+/// Builds the request topic of the card with the given prefix, see `get_card_mqtt_settings`.
+fn card_request_topic(topic_prefix: &str, card_number: &str) -> String {
+    format!("{}/{}/request", topic_prefix, card_number)
+}
+
+/// Builds the response topic of the request topic. Only the last level is changed,
+/// a custom prefix may contain "request".
+pub fn response_topic(request_topic: &str) -> String {
+    match request_topic.strip_suffix("/request") {
+        Some(base) => format!("{}/response", base),
+        None => request_topic.replace("request", "response"),
+    }
+}
+
+/// Converts the configured QoS level, the levels are validated when the config is saved.
+fn qos_from_level(level: Option<u8>) -> QoS {
+    match level {
+        Some(0) => QoS::AtMostOnce,
+        Some(2) => QoS::ExactlyOnce,
+        _ => QoS::AtLeastOnce,
+    }
+}
+
+/// Composes the MQTT client ID of the card from the app ident and the card number.
+fn compose_client_id(ident: &str, card_number: &str) -> String {
+    if ident.is_empty() {
+        card_number.to_string()
+    } else {
+        format!("{}-{}", ident, card_number)
+    }
+}
+
+/// User properties sent with every card connection, so the server can tell the bridges apart.
+fn connection_properties(ident: &str, card_number: &str) -> Vec<(String, String)> {
+    let hostname = sys_info::hostname().unwrap_or_else(|_| "Unknown".to_string());
+
+    vec![
+        ("ident".to_string(), ident.to_string()),
+        ("hostname".to_string(), hostname),
+        ("card_number".to_string(), card_number.to_string()),
+    ]
+}
+
+/// Ensures an MQTT connection for the specified client ID (card number).
+///
+/// Only one connection per card number is allowed. If the card number is already active
+/// in another reader, the already active card is kept and a conflict notification is sent.
+/// The ICCID is the one the caller has already read, empty for a card mapped by its ATR.
+/// Returns whether the card task is started, false if the card is refused or already active.
 pub async fn ensure_connection(
     reader_name: &CStr,
     client_id: String,
     atr: String,
+    iccid: String,
     managed_card: ManagedCard,
-) {
+) -> bool {
     // Return early if the client_id is empty, as we cannot ensure a connection without a valid ID
     if client_id.is_empty() {
         log::warn!(
             "Reader: {:?}. ClientID is empty. Cannot ensure connection.",
             reader_name
         );
-        return;
+        return false;
+    }
+
+    // Disabled cards keep their configuration, but are not connected to the broker
+    if !is_card_enabled(&client_id) {
+        log::info!("{} | Card is disabled. Connection is skipped.", client_id);
+        return false;
+    }
+
+    // A card pinned to another reader, e.g. left in a spare reader, is not connected
+    let reader_name_lossy = reader_name.to_string_lossy();
+    if !is_preferred_reader(&client_id, &reader_name_lossy) {
+        log::warn!(
+            "{} | Card is pinned to another reader. Card in reader {} is ignored.",
+            client_id,
+            reader_name_lossy
+        );
+
+        let payload = NotificationPayload {
+            notification_type: "reader".to_string(),
+            message: format!(
+                "Card {} is inserted in reader {}, but it is pinned to another reader. The card is not connected.",
+                client_id, reader_name_lossy
+            ),
+        };
+        emit_notification_event("global-notification", payload);
+        return false;
+    }
+
+    // Restricted deployments: only the listed cards are connected
+    if !is_card_allowed(&client_id) {
+        log::warn!(
+            "{} | Card is not in the allowed cards. Connection is refused.",
+            client_id
+        );
+
+        let payload = NotificationPayload {
+            notification_type: "not_authorized".to_string(),
+            message: format!("Card {} is not authorized to connect.", client_id),
+        };
+        emit_notification_event("global-notification", payload);
+        return false;
     }
 
     // Unlock task_pool mutex
@@ -55,10 +204,32 @@ pub async fn ensure_connection(
     // This part of function checks if a connection already exists for the given client ID
     // in the task pool. If not, it initiates a new connection. This is useful for maintaining
     // a list of active MQTT connections and ensuring that each client ID is only connected once.
-    let exists = task_pool.iter().any(|card| card.client_id == client_id);
+    let existing = task_pool.iter().find(|card| card.client_id == client_id);
     // If existing connection is found, then return, no add a new connection for this client_id
-    if exists {
-        return;
+    if let Some(existing) = existing {
+        // Same card number in another reader: the same physical card moved between readers
+        // or two cards with the same ICCID. The card that is already active wins, the second
+        // one is ignored until the first one is removed and the readers are synced again.
+        let active_reader = existing.reader_name.as_deref().unwrap_or("unknown");
+        let new_reader = reader_name.to_string_lossy();
+        if active_reader != new_reader {
+            log::warn!(
+                "{} | Card is already active in reader {}. Card in reader {} is ignored.",
+                client_id,
+                active_reader,
+                new_reader
+            );
+
+            let payload = NotificationPayload {
+                notification_type: "conflict".to_string(),
+                message: format!(
+                    "Card {} is already active in reader {}. The card in reader {} is ignored.",
+                    client_id, active_reader, new_reader
+                ),
+            };
+            emit_notification_event("global-notification", payload);
+        }
+        return false;
     }
 
     // Getting server data from the cache
@@ -70,23 +241,42 @@ pub async fn ensure_connection(
         }
         Err(e) => {
             log::error!("Error: {}", e);
-            return;
+            return false;
         }
     };
 
     //////////////////////////////////////////////////
     //  Create a new client ID for the MQTT connection
     //////////////////////////////////////////////////
-    let mut mqtt_options = MqttOptions::new(&client_id, &host, port);
+    // The card number alone collides when two machines hold the same company card,
+    // so the MQTT client ID is prefixed with the app ident. The card number is kept
+    // as the task pool key and for the topic routing.
+    let ident = get_from_cache(CacheSection::Ident, "ident");
+    let mqtt_client_id = compose_client_id(&ident, &client_id);
+
+    let mut mqtt_options = MqttOptions::new(&mqtt_client_id, &host, port);
     // mqtt_options.set_credentials(flespi_token, "");
-    mqtt_options.set_keep_alive(Duration::from_secs(120));
-    // log::debug!("mqtt_options: {:?}", mqtt_options);
-    log::debug!("mqtt_options: {:?}", mqtt_options);
+    mqtt_options.set_keep_alive(get_server_config().keep_alive_interval());
+    // Fail fast when the broker never accepts the connection (e.g. firewall drops)
+    mqtt_options.set_connection_timeout(get_server_config().connection_timeout_secs());
+    if let Some(credentials) = get_server_credentials() {
+        mqtt_options.set_credentials(credentials.username, credentials.password);
+    }
+    mqtt_options.set_user_properties(connection_properties(&ident, &client_id));
+    // The options are not logged as a whole, their Debug output contains the credentials
+    log::debug!(
+        "mqtt_options: host {}:{}, client_id {}, keep_alive {:?}",
+        host,
+        port,
+        mqtt_client_id,
+        mqtt_options.keep_alive()
+    );
 
     // Create a new asynchronous MQTT client and its associated event loop
     // `mqtt_options` specifies the configuration for the MQTT connection
-    // `10` is the capacity of the internal channel used by the event loop for buffering operations
-    let (mqtt_client, mut eventloop) = AsyncClient::new(mqtt_options, 10);
+    // The capacity of the internal channel buffering the requests is configurable, see `channel_capacity`
+    let (mqtt_client, mut eventloop) =
+        AsyncClient::new(mqtt_options, get_server_config().channel_capacity());
 
     let mqtt_clinet_cloned = mqtt_client.clone();
     let client_id_cloned = client_id.clone();
@@ -96,36 +286,202 @@ pub async fn ensure_connection(
 
     let atr_clone = atr.clone(); // Using ATR inside async_runtime
 
+    // Topics and QoS of the card, a card may be bound to another backend channel
+    let (topic_prefix, qos_level) = get_card_mqtt_settings(&client_id);
+    let card_topic = card_request_topic(&topic_prefix, &client_id);
+    let qos = qos_from_level(qos_level);
+    let managed_card_cloned = managed_card.clone(); // Shared card handle kept in the task pool
+
     // format of the logging header
     let log_header: String = format!("{} |", client_id);
 
     let mut is_online: bool = false; // flag to control the card connection (to the server) status
+    let online_state = Arc::new(AtomicBool::new(false)); // Connection status shared with the task pool
+    let online_state_cloned = Arc::clone(&online_state);
+    let metrics = Arc::new(StdMutex::new(ConnectionMetrics::default())); // Reconnects and uptime shared with the task pool
+    let metrics_cloned = Arc::clone(&metrics);
     let mut was_online = false; // Flag to track the previous connection status
-    let mut auth_process: bool = false; // Flag to control the authentication process
+    let auth_process = Arc::new(AtomicBool::new(false)); // Flag to control the authentication process, shared with the task pool
+    let auth_process_cloned = Arc::clone(&auth_process);
+    let stop = Arc::new(Notify::new()); // Stop request of remove_connections, handled while polling or in the backoff
+    let stop_cloned = Arc::clone(&stop);
+    let paused = Arc::new(AtomicBool::new(false)); // Requests are not sent to the card, see pause_card
+    let paused_cloned = Arc::clone(&paused);
+    let release_exchange = Arc::new(Notify::new()); // The authentication was reset, see reset_card_auth
+    let release_exchange_cloned = Arc::clone(&release_exchange);
 
     // create async task for the mqtt client
     let handle: JoinHandle<()> = async_runtime::spawn(async move {
-        let iccid: String = managed_card
-            .get_iccid()
-            .await
-            .expect("ICCID must be initialized");
+        // Reports a panic of this task and removes the card from the task pool
+        let _panic_guard = TaskPanicGuard {
+            card_number: client_id_cloned.clone(),
+        };
+
+        // State of the present card, every event to the frontend is based on it
+        let card_state = TachoState {
+            iccid,
+            reader_name: reader_name.to_string_lossy().into(),
+            card_state: "PRESENT".into(),
+            card_number: client_id_cloned.clone(),
+            online: None,
+            authentication: None,
+            generation: Some(managed_card.get_generation().await),
+            atr: Some(atr_clone.clone()),
+            protocol: Some(protocol_name(managed_card.protocol())),
+            errored: None,
+        };
+
+        // Workshop cards report the PIN tries, so the UI can warn before the card is blocked.
+        // Opt-in: the SELECT and the empty VERIFY are not requested by the server
+        if get_smart_card_config().pin_tries_on_connect {
+            let pin_tries = {
+                let _exchange = managed_card.lock_exchange().await;
+                managed_card.read_pin_tries().await
+            };
+            match pin_tries {
+                Ok(PinTries::Unknown) => {}
+                Ok(pin_tries) => {
+                    if matches!(pin_tries, PinTries::Remaining(1) | PinTries::Blocked) {
+                        log::warn!("{} PIN try counter is low: {:?}", log_header, pin_tries);
+                    }
+                    emit_pin_tries_event(
+                        "global-card-pin-tries",
+                        client_id_cloned.clone(),
+                        pin_tries,
+                    );
+                }
+                Err(e) => log::debug!("{} PIN try counter is not read: {}", log_header, e),
+            }
+        }
+
+        // Held from the start of the authentication to its finish, see `lock_exchange`
+        let mut exchange_guard = None;
+
+        // Idle timeout: the card is released when the server sends nothing for this long
+        let idle_timeout = get_smart_card_config().idle_timeout();
+        let mut last_activity = tokio::time::Instant::now();
 
         loop {
-            match eventloop.poll().await {
+            // The exchange of an authentication the server went quiet on expires
+            let exchange_deadline = exchange_guard
+                .as_ref()
+                .map(|_| last_activity + EXCHANGE_IDLE_TIMEOUT);
+
+            let wake = match idle_timeout {
+                Some(idle_timeout) => {
+                    match tokio::time::timeout_at(
+                        last_activity + idle_timeout,
+                        next_wake(
+                            &mut eventloop,
+                            &stop_cloned,
+                            &release_exchange_cloned,
+                            exchange_deadline,
+                        ),
+                    )
+                    .await
+                    {
+                        Ok(wake) => wake,
+                        Err(_) => {
+                            log::info!(
+                                "{} No requests for {:?}. Card is disconnected as idle.",
+                                log_header,
+                                idle_timeout
+                            );
+
+                            // Drive the event loop until the DISCONNECT is sent, so the server
+                            // sees a clean disconnect. The loop ends with an error after it.
+                            let _ = mqtt_client.disconnect().await;
+                            let _ = tokio::time::timeout(Duration::from_secs(2), async {
+                                while eventloop.poll().await.is_ok() {}
+                            })
+                            .await;
+
+                            online_state_cloned.store(false, Ordering::Relaxed);
+                            emit_event(
+                                "global-cards-sync",
+                                card_state.with_status(Some(false), None),
+                            );
+
+                            let payload = NotificationPayload {
+                                notification_type: "idle".to_string(),
+                                message: format!(
+                                    "Card {} is disconnected after {} s without requests. Reinsert or sync the card to connect it again.",
+                                    client_id_cloned,
+                                    idle_timeout.as_secs()
+                                ),
+                            };
+                            emit_notification_event("global-notification", payload);
+
+                            // Releases the reader handle too, the task pool is locked in a separate task
+                            let card_number = client_id_cloned.clone();
+                            async_runtime::spawn(async move {
+                                remove_connections(vec![card_number]).await;
+                            });
+                            return;
+                        }
+                    }
+                }
+                None => {
+                    next_wake(
+                        &mut eventloop,
+                        &stop_cloned,
+                        &release_exchange_cloned,
+                        exchange_deadline,
+                    )
+                    .await
+                }
+            };
+
+            let polled = match wake {
+                TaskWake::Polled(polled) => polled,
+                // Asked to stop while connected, see `stop_tasks`
+                TaskWake::Stop => break,
+                TaskWake::ExchangeReleased => {
+                    // The card is already reset by `reset_card_auth`
+                    if exchange_guard.take().is_some() {
+                        log::info!(
+                            "{} Exchange of the reset authentication is released",
+                            log_header
+                        );
+                    }
+                    continue;
+                }
+                TaskWake::ExchangeExpired => {
+                    log::warn!(
+                        "{} No requests for {:?} during the authentication. The card is reset.",
+                        log_header,
+                        EXCHANGE_IDLE_TIMEOUT
+                    );
+
+                    if let Err(e) = managed_card.reconnect().await {
+                        drop_card_task(
+                            client_id_cloned.clone(),
+                            format!("Card reset failed: {}", e),
+                        );
+                        return;
+                    }
+                    auth_process_cloned.store(false, Ordering::Relaxed);
+                    exchange_guard = None;
+
+                    emit_event(
+                        "global-cards-sync",
+                        card_state.with_status(Some(is_online), Some(false)),
+                    );
+                    continue;
+                }
+            };
+
+            match polled {
                 Ok(notification) => {
                     if !is_online {
                         is_online = true;
+                        online_state_cloned.store(true, Ordering::Relaxed);
                         if !was_online {
                             was_online = true;
                             // Send the global-cards-sync event to the frontend that card is connected
                             emit_event(
                                 "global-cards-sync",
-                                iccid.clone().into(),
-                                reader_name.to_string_lossy().into(),
-                                "PRESENT".into(),
-                                client_id_cloned.clone(),
-                                Some(true),
-                                None,
+                                card_state.with_status(Some(true), None),
                             );
                         }
                     }
@@ -134,6 +490,8 @@ pub async fn ensure_connection(
 
                     match notification {
                         Event::Incoming(Incoming::Publish(publish)) => {
+                            last_activity = tokio::time::Instant::now();
+
                             // Extracting the topic from the incoming data
                             let topic_str = match std::str::from_utf8(&publish.topic) {
                                 Ok(str) => str,
@@ -150,144 +508,242 @@ pub async fn ensure_connection(
                             let topic = topic_str.to_string();
                             // The contents of response and request are the same.
                             // Card number and parcel ID. So we just change the initial topic
-                            let topic_ack = topic.replace("request", "response");
-                            // serializable data to interpret it as json
-                            match serde_json::from_slice::<Value>(&publish.payload) {
-                                Ok(json_payload) => {
-                                    log::debug!("Parsed JSON payload: {:?}", json_payload);
+                            let topic_ack = response_topic(&topic);
+                            // deserialize and validate the request before touching the card
+                            match parse_auth_request(&publish.payload) {
+                                // The finish is processed anyway, it releases the exchange and resets the card
+                                Ok(request)
+                                    if paused_cloned.load(Ordering::Relaxed) && !request.finish =>
+                                {
+                                    // The card is not touched, the server can retry after the resume
+                                    log::info!(
+                                        "{} Request is not processed, the card is paused",
+                                        log_header
+                                    );
+                                    let payload_ack = process_paused_request(&request);
+                                    if let Err(e) = mqtt_client
+                                        .publish(topic_ack, qos, false, payload_ack)
+                                        .await
+                                    {
+                                        log::error!(
+                                            "{} Failed to publish the answer: {:?}",
+                                            log_header,
+                                            e
+                                        );
+                                    }
+                                }
+                                Ok(request) => {
+                                    log::debug!("Parsed request: {:?}", request);
 
                                     let mut payload_ack = String::new();
 
-                                    // Check for the presence of the "finish" parameter
-                                    if let Some(finish_value) =
-                                        json_payload.get("finish").and_then(|v| v.as_bool())
-                                    {
-                                        log::debug!(
-                                            "{} Finish parameter: {}",
-                                            log_header,
-                                            finish_value
+                                    let finish_value = request.finish;
+                                    log::debug!(
+                                        "{} Finish parameter: {}",
+                                        log_header,
+                                        finish_value
+                                    );
+
+                                    // Processing the "finish" parameter depending on its value
+                                    if finish_value {
+                                        // Send the global-cards-sync event to the frontend that card is connected
+                                        emit_event(
+                                            "global-cards-sync",
+                                            card_state.with_status(Some(true), Some(false)),
                                         );
 
-                                        // Processing the "finish" parameter depending on its value
-                                        if finish_value {
-                                            // Send the global-cards-sync event to the frontend that card is connected
-                                            emit_event(
-                                                "global-cards-sync",
-                                                iccid.clone().into(),
-                                                reader_name.to_string_lossy().into(),
-                                                "PRESENT".into(),
-                                                client_id_cloned.clone(),
-                                                Some(true),
-                                                Some(false),
-                                            );
+                                        log::info!("Authentication process is finished");
 
-                                            log::info!("Authentication process is finished");
+                                        // Reset the card to its original state, an unusable card is dropped
+                                        if let Err(e) = managed_card.reconnect().await {
+                                            let reason = format!("Card reset failed: {}", e);
+                                            answer_error_and_disconnect(
+                                                &mqtt_client,
+                                                &mut eventloop,
+                                                topic_ack,
+                                                qos,
+                                                &reason,
+                                            )
+                                            .await;
+                                            drop_card_task(client_id_cloned.clone(), reason);
+                                            return;
+                                        }
 
-                                            // Reset the card to its original state
-                                            managed_card.reconnect().await;
+                                        payload_ack = process_rapdu_mqtt_hex("".to_string());
 
-                                            payload_ack = process_rapdu_mqtt_hex("".to_string());
+                                        // Authorization process is finished
+                                        auth_process_cloned.store(false, Ordering::Relaxed);
+                                        exchange_guard = None;
 
-                                            auth_process = false; // Authorization process is finished
+                                    // handle the case when finish == true
+                                    } else {
+                                        // finish flag is false here
+                                        // The card belongs to the authentication until the finish
+                                        if exchange_guard.is_none() {
+                                            exchange_guard =
+                                                Some(managed_card.lock_exchange().await);
+                                        }
 
-                                        // handle the case when finish == true
-                                        } else {
-                                            // finish flag is false here
-                                            // PROCESS AUTHORIZATION WITH APDU COMMUNICATION
-                                            // The "hex" parameter contains the apdu instruction that needs to be transferred to the card
-                                            if let Some(hex_value) =
-                                                json_payload.get("payload").and_then(|v| v.as_str())
+                                        // PROCESS AUTHORIZATION WITH APDU COMMUNICATION
+                                        // The "payloads" parameter contains related APDUs to be sent in one card transaction
+                                        if let Some(apdus) = request.payloads {
+                                            match managed_card
+                                                .send_apdu_batch(&apdus, &client_id_cloned)
+                                                .await
                                             {
-                                                log::debug!(
-                                                    "{} TRACKER: Payload hex value: {}",
-                                                    log_header,
-                                                    hex_value
-                                                );
+                                                Ok(responses) => {
+                                                    // Send the global-cards-sync event to the frontend that card is connected,
+                                                    // only if the last status word of the batch counts as success
+                                                    let last_response =
+                                                        responses.last().map_or("", String::as_str);
+                                                    emit_auth_progress(
+                                                        &card_state,
+                                                        &managed_card,
+                                                        last_response,
+                                                        &log_header,
+                                                    );
 
-                                                let mut rapdu_mqtt_hex = String::new(); // empty string for the response
+                                                    // Authorization process is in progress
+                                                    auth_process_cloned
+                                                        .store(true, Ordering::Relaxed);
 
-                                                if hex_value.is_empty() {
-                                                    // This case is needed to reset the card when authorization is not completed, otherwise the card will not respond to commands correctly.
-                                                    if auth_process {
-                                                        // Reset the card to its original state
-                                                        managed_card.reconnect().await;
+                                                    payload_ack =
+                                                        process_rapdu_mqtt_hex_batch(responses);
+                                                }
+                                                Err(interrupted) => {
+                                                    // The server abandons the authentication, the card is released
+                                                    if interrupted.reset {
+                                                        emit_event(
+                                                            "global-cards-sync",
+                                                            card_state.with_status(
+                                                                Some(true),
+                                                                Some(false),
+                                                            ),
+                                                        );
                                                     }
+                                                    auth_process_cloned
+                                                        .store(false, Ordering::Relaxed);
+                                                    exchange_guard = None;
 
-                                                    // If the input value is empty, then pass the ATR to the server.
-                                                    rapdu_mqtt_hex = atr_clone.clone();
-                                                    log::info!("Authentication process is started");
-
-                                                    // Send the global-cards-sync event to the frontend that card is connected
-                                                    emit_event(
-                                                        "global-cards-sync",
-                                                        iccid.clone().into(),
-                                                        reader_name.to_string_lossy().into(),
-                                                        "PRESENT".into(),
-                                                        client_id_cloned.clone(),
-                                                        Some(true),
-                                                        Some(false),
+                                                    payload_ack = process_interrupted_request(
+                                                        interrupted,
+                                                        true,
                                                     );
-                                                } else {
-                                                    // // Otherwise, the logic for exchanging messages with the card.
-                                                    rapdu_mqtt_hex = managed_card
-                                                        .send_apdu(&hex_value, &client_id_cloned)
-                                                        .await;
+                                                }
+                                            }
+                                        } else if let Some(hex_value) = request.payload {
+                                            // The "hex" parameter contains the apdu instruction that needs to be transferred to the card
+                                            log::debug!(
+                                                "{} TRACKER: Payload hex value: {}",
+                                                log_header,
+                                                hex_value
+                                            );
 
-                                                    // Send the global-cards-sync event to the frontend that card is connected
-                                                    emit_event(
-                                                        "global-cards-sync",
-                                                        iccid.clone().into(),
-                                                        reader_name.to_string_lossy().into(),
-                                                        "PRESENT".into(),
-                                                        client_id_cloned.clone(),
-                                                        Some(true),
-                                                        Some(true),
-                                                    );
+                                            let mut rapdu_mqtt_hex = String::new(); // empty string for the response
+                                            let mut interrupted_by = None; // The card is removed or reset during the APDU.
 
-                                                    auth_process = true; // Authorization process is in progress
+                                            if hex_value.is_empty() {
+                                                // This case is needed to reset the card when authorization is not completed, otherwise the card will not respond to commands correctly.
+                                                if auth_process_cloned.load(Ordering::Relaxed) {
+                                                    // Reset the card to its original state, an unusable card is dropped
+                                                    if let Err(e) = managed_card.reconnect().await {
+                                                        let reason =
+                                                            format!("Card reset failed: {}", e);
+                                                        answer_error_and_disconnect(
+                                                            &mqtt_client,
+                                                            &mut eventloop,
+                                                            topic_ack,
+                                                            qos,
+                                                            &reason,
+                                                        )
+                                                        .await;
+                                                        drop_card_task(
+                                                            client_id_cloned.clone(),
+                                                            reason,
+                                                        );
+                                                        return;
+                                                    }
                                                 }
 
-                                                payload_ack =
-                                                    process_rapdu_mqtt_hex(rapdu_mqtt_hex);
+                                                // If the input value is empty, then pass the ATR to the server.
+                                                rapdu_mqtt_hex = atr_clone.clone();
+                                                log::info!("Authentication process is started");
 
-                                                // log::info!("finish_value: {}", finish_value);
-                                            } else {
-                                                log::error!(
-                                                    "{} Hex value not found or is not a string",
-                                                    log_header
+                                                // Send the global-cards-sync event to the frontend that card is connected
+                                                emit_event(
+                                                    "global-cards-sync",
+                                                    card_state.with_status(Some(true), Some(false)),
                                                 );
+                                            } else {
+                                                // // Otherwise, the logic for exchanging messages with the card.
+                                                match managed_card
+                                                    .send_apdu(&hex_value, &client_id_cloned)
+                                                    .await
+                                                {
+                                                    Ok(response) => {
+                                                        rapdu_mqtt_hex = response;
+
+                                                        // Send the global-cards-sync event to the frontend that card is connected,
+                                                        // only if the status word counts as success
+                                                        emit_auth_progress(
+                                                            &card_state,
+                                                            &managed_card,
+                                                            &rapdu_mqtt_hex,
+                                                            &log_header,
+                                                        );
+
+                                                        // Authorization process is in progress
+                                                        auth_process_cloned
+                                                            .store(true, Ordering::Relaxed);
+                                                    }
+                                                    Err(interrupted) => {
+                                                        // The server abandons the authentication, the card is released
+                                                        if interrupted.reset {
+                                                            emit_event(
+                                                                "global-cards-sync",
+                                                                card_state.with_status(
+                                                                    Some(true),
+                                                                    Some(false),
+                                                                ),
+                                                            );
+                                                        }
+                                                        auth_process_cloned
+                                                            .store(false, Ordering::Relaxed);
+                                                        exchange_guard = None;
+                                                        interrupted_by = Some(interrupted);
+                                                    }
+                                                }
                                             }
 
-                                            log::debug!(
-                                                "{} CARD: Payload hex value: {}",
-                                                log_header,
-                                                payload_ack
-                                            );
-                                        }
+                                            payload_ack = match interrupted_by {
+                                                Some(interrupted) => {
+                                                    process_interrupted_request(interrupted, false)
+                                                }
+                                                None => process_rapdu_mqtt_hex(rapdu_mqtt_hex),
+                                            };
 
-                                        // publish a message to the channel
-                                        let publish_result = mqtt_client
-                                            .publish(
-                                                topic_ack,
-                                                QoS::AtLeastOnce,
-                                                false,
-                                                payload_ack,
-                                            )
-                                            .await;
-                                        match publish_result {
-                                            Ok(_) => println!("Message published successfully"),
-                                            Err(e) => println!("Error sending message: {:?}", e),
+                                            // log::info!("finish_value: {}", finish_value);
                                         }
-                                    } else {
-                                        log::error!(
-                                            "{} Finish parameter not found or is not a boolean",
-                                            log_header
+
+                                        log::debug!(
+                                            "{} CARD: Payload hex value: {}",
+                                            log_header,
+                                            payload_ack
                                         );
                                     }
+
+                                    // publish a message to the channel
+                                    let publish_result = mqtt_client
+                                        .publish(topic_ack, qos, false, payload_ack)
+                                        .await;
+                                    match publish_result {
+                                        Ok(_) => println!("Message published successfully"),
+                                        Err(e) => println!("Error sending message: {:?}", e),
+                                    }
                                 }
                                 Err(e) => {
                                     log::error!(
-                                        "{} parsing JSON payload issue: {:?}",
+                                        "{} Malformed request is rejected: {}",
                                         log_header,
                                         e
                                     );
@@ -295,10 +751,45 @@ pub async fn ensure_connection(
                             }
                         }
                         Event::Incoming(Incoming::ConnAck(..)) => {
+                            metrics_cloned.lock().unwrap().record_connected();
+                            clear_card_error(&client_id_cloned);
                             log::info!(
                                 "{} Connection to the server has been successfully established.",
                                 log_header
-                            )
+                            );
+
+                            // Subscribe explicitly on every (re)connect, the broker may drop
+                            // subscriptions after a restart or a clean session.
+                            let topic = card_topic.clone();
+                            match mqtt_client.subscribe(topic.clone(), qos).await {
+                                Ok(_) => {
+                                    log::debug!("{} Subscribe request sent: {}", log_header, topic)
+                                }
+                                Err(e) => log::error!(
+                                    "{} Failed to subscribe to {}: {:?}",
+                                    log_header,
+                                    topic,
+                                    e
+                                ),
+                            }
+                        }
+                        Event::Incoming(Incoming::SubAck(suback)) => {
+                            // Confirm that the broker accepted the subscription
+                            let rejected: Vec<_> = suback
+                                .return_codes
+                                .iter()
+                                .filter(|code| !matches!(code, SubscribeReasonCode::Success(_)))
+                                .collect();
+
+                            if rejected.is_empty() {
+                                log::info!("{} Subscribed to the request topic.", log_header);
+                            } else {
+                                log::error!(
+                                    "{} Subscription rejected by the server: {:?}",
+                                    log_header,
+                                    rejected
+                                );
+                            }
                         }
                         Event::Incoming(Incoming::PingResp(..)) => {
                             log::debug!("{} Ping response received from the server.", log_header);
@@ -306,12 +797,7 @@ pub async fn ensure_connection(
                             // Send the global-cards-sync event to the frontend that card is connected
                             emit_event(
                                 "global-cards-sync",
-                                iccid.clone().into(),
-                                reader_name.to_string_lossy().into(),
-                                "PRESENT".into(),
-                                client_id_cloned.clone(),
-                                Some(true),
-                                Some(false),
+                                card_state.with_status(Some(true), Some(false)),
                             );
                         }
                         _ => {} // This handles any other events that you haven't explicitly matched above
@@ -321,17 +807,33 @@ pub async fn ensure_connection(
                     // Send the global-cards-sync event to the frontend that card is connected
                     emit_event(
                         "global-cards-sync",
-                        iccid.clone().into(),
-                        reader_name.to_string_lossy().into(),
-                        "PRESENT".into(),
-                        client_id_cloned.clone(),
-                        Some(false),
-                        None,
+                        card_state.with_status(Some(false), None),
                     );
 
                     is_online = false;
+                    online_state_cloned.store(false, Ordering::Relaxed);
+                    metrics_cloned.lock().unwrap().record_error();
                     was_online = false; // Reset the flag when the connection is lost
 
+                    // The errors are expected during a planned broker downtime, they are only traced
+                    if let Some(retry) = get_server_config().maintenance_retry_interval() {
+                        log::debug!(
+                            "{} Connection error in the maintenance window: {}",
+                            log_header,
+                            e
+                        );
+                        if backoff_or_stop(&stop_cloned, retry).await {
+                            break;
+                        }
+                        continue;
+                    }
+
+                    report_broker_error(&client_id_cloned, &e);
+                    record_card_error(
+                        &client_id_cloned,
+                        format!("Server connection failed: {}", e),
+                    );
+
                     match e {
                         ConnectionError::Io(ref io_err) => match io_err.kind() {
                             ErrorKind::ConnectionAborted => log::warn!("{} Can't establish a connection to a remote server.", log_header),
@@ -339,6 +841,7 @@ pub async fn ensure_connection(
                             ErrorKind::TimedOut => log::warn!("{} Connection timeout. The server may be down or the network is unstable.", log_header),
                             _ => log::error!("{} An IO error occurred.", log_header),
                         },
+                        ConnectionError::Timeout(_) => log::warn!("{} Connection timeout. The server did not accept the connection in time.", log_header),
                         ConnectionError::MqttState(ServerDisconnect { .. }) => log::warn!("{} The connection was terminated on the server side. Most likely the user has turned off the channel/device.", log_header),
                         ConnectionError::MqttState(AwaitPingResp { .. }) => {
                             log::warn!("{} Awaiting PING response from the server. The connection might be unstable.", log_header);
@@ -353,10 +856,27 @@ pub async fn ensure_connection(
                         },
                     };
                     // Reconnection timeout for handled errors
-                    tokio::time::sleep(Duration::from_secs(SLEEP_DURATION_SECS)).await;
+                    if backoff_or_stop(&stop_cloned, Duration::from_secs(SLEEP_DURATION_SECS)).await
+                    {
+                        break;
+                    }
                 }
             }
         }
+
+        // Ordered shutdown: the exchange is released and an unfinished authentication is reset,
+        // the card is disconnected when the last handle is dropped at the end of the task.
+        drop(exchange_guard);
+        if auth_process_cloned.load(Ordering::Relaxed) {
+            if let Err(e) = managed_card.reconnect().await {
+                log::warn!(
+                    "{} Card is not reset before the task stops: {}",
+                    log_header,
+                    e
+                );
+            }
+        }
+        log::info!("{} Card task is stopped.", log_header);
     });
 
     task_pool.push(ProcessingCard {
@@ -365,6 +885,13 @@ pub async fn ensure_connection(
         atr: Some(atr),
         mqtt_client: mqtt_clinet_cloned,
         task_handle: handle,
+        managed_card: Some(managed_card_cloned),
+        online: online_state,
+        metrics,
+        auth_process,
+        stop,
+        paused,
+        release_exchange,
     });
 
     for (i, card) in task_pool.iter().enumerate() {
@@ -376,53 +903,301 @@ pub async fn ensure_connection(
             card.atr.as_deref().unwrap_or("unknown"),
         );
     }
+
+    true
 }
 
-/// Terminates connections for the specified client IDs (card numbers).
-pub async fn remove_connections(client_ids: Vec<String>) {
-    log::debug!("Removing connections for client_ids: {:?}", client_ids);
+/// Emits the authentication progress of the card if the status word of the response is one of
+/// the configured success status words. The response is forwarded to the server regardless.
+fn emit_auth_progress(
+    card_state: &TachoState,
+    managed_card: &ManagedCard,
+    response: &str,
+    log_header: &str,
+) {
+    if !get_smart_card_config().is_success_status(response) {
+        // Only the status word is logged, the response data may be sensitive
+        let status_word = response
+            .len()
+            .checked_sub(4)
+            .and_then(|start| response.get(start..))
+            .unwrap_or_default();
+        log::debug!(
+            "{} Status word {} is not a success status, no authentication progress",
+            log_header,
+            status_word
+        );
+        return;
+    }
 
-    // Lock the task pool
-    let mut task_pool = TASK_POOL.lock().await;
+    emit_event(
+        "global-cards-sync",
+        card_state
+            .with_status(Some(true), Some(true))
+            .with_errored(managed_card.is_errored()),
+    );
+}
 
-    for client_id in client_ids {
-        // Find the index of the card with the matching client_id
-        if let Some(index) = task_pool
-            .iter()
-            .position(|card| card.client_id == client_id)
-        {
-            let card = task_pool.remove(index);
-            card.task_handle.abort();
+/// Stops the card processing after an unrecoverable error in its task.
+///
+/// The user is notified, and the card is removed from the task pool,
+/// so it is created again on the next insertion event.
+fn drop_card_task(card_number: String, reason: String) {
+    log::error!("{} | Card processing is stopped. {}", card_number, reason);
+    record_card_error(&card_number, reason.clone());
+
+    let payload = NotificationPayload {
+        notification_type: "card".to_string(),
+        message: format!("Card {} processing is stopped. {}", card_number, reason),
+    };
+    emit_notification_event("global-notification", payload);
 
-            log::debug!(
-                "TASK_POOL: Connection terminated for client_id: {}, reader: {}, atr: {}",
-                card.client_id,
-                card.reader_name.as_deref().unwrap_or("unknown"),
-                card.atr.as_deref().unwrap_or("unknown"),
+    // The task pool can't be locked from the drop of a panicking task, so it is done in a separate task
+    async_runtime::spawn(async move {
+        remove_connections(vec![card_number]).await;
+    });
+}
+
+/// Guard living inside the card task. If the task panics, the unwinding drops the guard,
+/// which reports the panic instead of losing it silently. An abort of the task is not reported.
+struct TaskPanicGuard {
+    card_number: String,
+}
+
+impl Drop for TaskPanicGuard {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            drop_card_task(
+                self.card_number.clone(),
+                "Unexpected panic in the card task.".to_string(),
             );
         }
     }
 }
 
-/// Terminates all active card-related MQTT connections and clears the task pool.
-pub async fn remove_connections_all() {
-    log::debug!("Removing all card connections...");
+/// Probes the broker reachability before the server settings are saved.
+/// A separate short-lived MQTT client is used, so the active connections are not disturbed.
+#[tauri::command]
+pub async fn test_server_connection(host: String) -> Result<(), String> {
+    log::debug!("Testing the server connection: {}", host);
 
-    // Lock the task pool
-    let mut task_pool = TASK_POOL.lock().await;
+    let (host_name, port) = split_host_to_parts(&host)?;
+
+    // Resolve the name first, so a DNS failure is not reported as a generic I/O error
+    let resolved = tokio::net::lookup_host((host_name.as_str(), port))
+        .await
+        .map(|mut addrs| addrs.next().is_some())
+        .map_err(|e| format!("DNS lookup of {} failed: {}", host_name, e))?;
+    if !resolved {
+        return Err(format!("DNS lookup of {} returned no addresses", host_name));
+    }
+
+    let ident = get_from_cache(CacheSection::Ident, "ident");
+    let connection_timeout = get_server_config().connection_timeout_secs();
+
+    let mut mqtt_options = MqttOptions::new(compose_client_id(&ident, "probe"), &host_name, port);
+    mqtt_options.set_connection_timeout(connection_timeout);
+    if let Some(credentials) = get_server_credentials() {
+        mqtt_options.set_credentials(credentials.username, credentials.password);
+    }
+
+    let (mqtt_client, mut eventloop) = AsyncClient::new(mqtt_options, 10);
+
+    let result = loop {
+        match eventloop.poll().await {
+            Ok(Event::Incoming(Incoming::ConnAck(_))) => break Ok(()),
+            Ok(_) => continue,
+            Err(ConnectionError::Timeout(_)) => {
+                break Err(format!("Connection timeout after {} s", connection_timeout))
+            }
+            Err(ConnectionError::Io(e)) if e.kind() == ErrorKind::ConnectionRefused => {
+                break Err(format!("Connection refused by {}:{}", host_name, port))
+            }
+            Err(ConnectionError::Io(e)) => break Err(format!("Network error: {}", e)),
+            Err(ConnectionError::Tls(e)) => break Err(format!("TLS error: {}", e)),
+            Err(ConnectionError::ConnectionRefused(code)) => {
+                break Err(format!("Broker refused the connection: {:?}", code))
+            }
+            Err(e) => break Err(format!("Connection failed: {}", e)),
+        }
+    };
+
+    if result.is_ok() {
+        let _ = mqtt_client.disconnect().await;
+    }
+
+    match &result {
+        Ok(()) => log::info!("Server {}:{} is reachable", host_name, port),
+        Err(e) => log::warn!("Server {}:{} is not reachable: {}", host_name, port, e),
+    }
+
+    result
+}
+
+/// Sleeps the reconnection backoff of the card task. Returns true when the task is asked
+/// to stop meanwhile, or before the sleep, see `remove_connections`.
+async fn backoff_or_stop(stop: &Notify, duration: Duration) -> bool {
+    tokio::select! {
+        _ = tokio::time::sleep(duration) => false,
+        _ = stop.notified() => true,
+    }
+}
+
+/// Reason the connected card task wakes up, see `next_wake`.
+enum TaskWake {
+    Polled(Result<Event, ConnectionError>), // Event of the broker connection.
+    Stop,                                   // Asked to stop, see `remove_connections`.
+    ExchangeReleased,                       // The authentication was reset, see `reset_card_auth`.
+    ExchangeExpired, // No requests during the authentication, see `EXCHANGE_IDLE_TIMEOUT`.
+}
+
+/// Polls the event loop of the card task, until an event of the broker connection, a stop
+/// request, a reset of the authentication or the expiry of the exchange deadline.
+async fn next_wake(
+    eventloop: &mut EventLoop,
+    stop: &Notify,
+    release_exchange: &Notify,
+    exchange_deadline: Option<tokio::time::Instant>,
+) -> TaskWake {
+    let exchange_expired = async {
+        match exchange_deadline {
+            Some(deadline) => tokio::time::sleep_until(deadline).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        polled = eventloop.poll() => TaskWake::Polled(polled),
+        _ = stop.notified() => TaskWake::Stop,
+        _ = release_exchange.notified() => TaskWake::ExchangeReleased,
+        _ = exchange_expired => TaskWake::ExchangeExpired,
+    }
+}
+
+/// Stops the tasks taken out of the task pool.
+///
+/// A card task is asked to stop and gets the grace period to leave its loop, while polling
+/// or in the backoff, so the card is reset and disconnected in order. A task still running
+/// after the grace period (e.g. stuck in a card transmit) and the app connection are aborted.
+async fn stop_tasks(cards: Vec<ProcessingCard>) {
+    let deadline = tokio::time::Instant::now() + get_smart_card_config().shutdown_grace();
+
+    for card in &cards {
+        if card.managed_card.is_some() {
+            card.stop.notify_one();
+        }
+    }
+
+    for mut card in cards {
+        let stopped = card.managed_card.is_some()
+            && tokio::time::timeout_at(deadline, &mut card.task_handle)
+                .await
+                .is_ok();
+        if !stopped {
+            card.task_handle.abort();
+        }
 
-    // Abort each task and log which client is being disconnected
-    for card in task_pool.drain(..) {
         log::debug!(
-            "TASK_POOL: Aborting task for client_id: {}, reader: {}, atr: {}",
+            "TASK_POOL: Connection terminated for client_id: {}, reader: {}, atr: {}, {}",
             card.client_id,
             card.reader_name.as_deref().unwrap_or("unknown"),
             card.atr.as_deref().unwrap_or("unknown"),
+            if stopped { "stopped" } else { "aborted" },
         );
-        card.task_handle.abort();
     }
+}
+
+/// Terminates connections for the specified client IDs (card numbers).
+pub async fn remove_connections(client_ids: Vec<String>) {
+    log::debug!("Removing connections for client_ids: {:?}", client_ids);
+
+    // The task pool is only locked to take the cards out, a stopping task may need it
+    let removed: Vec<ProcessingCard> = {
+        let mut task_pool = TASK_POOL.lock().await;
+        client_ids
+            .iter()
+            .filter_map(|client_id| {
+                task_pool
+                    .iter()
+                    .position(|card| &card.client_id == client_id)
+                    .map(|index| task_pool.remove(index))
+            })
+            .collect()
+    };
+
+    stop_tasks(removed).await;
+}
+
+/// Terminates the connections of all cards, the app connection is kept.
+pub async fn remove_card_connections() {
+    let client_ids: Vec<String> = TASK_POOL
+        .lock()
+        .await
+        .iter()
+        .filter(|card| card.managed_card.is_some())
+        .map(|card| card.client_id.clone())
+        .collect();
+
+    remove_connections(client_ids).await;
+}
+
+/// Terminates all MQTT connections, the cards and the app connection, and clears the task pool.
+/// The caller starts the app connection again when it is still needed, see `app_connection`.
+pub async fn remove_connections_all() {
+    log::debug!("Removing all connections...");
+
+    // Take all the tasks out of the pool and stop them without holding the lock
+    let removed: Vec<ProcessingCard> = TASK_POOL.lock().await.drain(..).collect();
+    stop_tasks(removed).await;
+
+    log::debug!("All connections have been terminated and the task pool has been cleared.");
+}
+
+/// Request of the server on the request topic of the card.
+#[derive(Debug, Deserialize)]
+struct AuthRequest {
+    finish: bool, // The authentication is finished and the card is reset.
+    #[serde(default)]
+    payload: Option<String>, // APDU in hex. Empty to start the authentication and get the ATR.
+    #[serde(default)]
+    payloads: Option<Vec<String>>, // Related APDUs in hex, sent in one card transaction.
+}
+
+impl AuthRequest {
+    /// Checks the request is complete and its APDUs are valid hex, see `normalize_apdu_hex`.
+    fn validate(&self) -> Result<(), String> {
+        if self.finish {
+            return Ok(());
+        }
+
+        match (&self.payloads, &self.payload) {
+            (Some(apdus), _) if apdus.is_empty() => Err("\"payloads\" is empty".to_string()),
+            (Some(apdus), _) => apdus
+                .iter()
+                .try_for_each(|apdu| match normalize_apdu_hex(apdu) {
+                    Ok(normalized) if normalized.is_empty() => {
+                        Err("\"payloads\" has an empty APDU".to_string())
+                    }
+                    Ok(_) => Ok(()),
+                    Err(e) => Err(format!(
+                        "\"payloads\" has an invalid APDU '{}': {}",
+                        apdu, e
+                    )),
+                }),
+            // An empty payload starts the authentication
+            (None, Some(apdu)) => normalize_apdu_hex(apdu)
+                .map(|_| ())
+                .map_err(|e| format!("\"payload\" is not a valid APDU '{}': {}", apdu, e)),
+            (None, None) => Err("neither \"payload\" nor \"payloads\" is present".to_string()),
+        }
+    }
+}
 
-    log::debug!("All card connections have been terminated and the task pool has been cleared.");
+/// Deserializes the request and checks it before anything is sent to the card.
+fn parse_auth_request(payload: &[u8]) -> Result<AuthRequest, String> {
+    let request: AuthRequest = serde_json::from_slice(payload).map_err(|e| e.to_string())?;
+    request.validate()?;
+    Ok(request)
 }
 
 fn process_rapdu_mqtt_hex(rapdu_mqtt_hex: String) -> String {
@@ -436,3 +1211,217 @@ fn process_rapdu_mqtt_hex(rapdu_mqtt_hex: String) -> String {
 
     payload_ack
 }
+
+/// Builds the answer to a request the card could not process, e.g. when the reset failed.
+fn process_error_request(error: &str) -> String {
+    serde_json::json!({
+        "payload": "",
+        "error": error,
+    })
+    .to_string()
+}
+
+/// Builds the answer to the request interrupted by the removed or reset card. The
+/// interruption has a field of its own, a status word in the payload would be ambiguous.
+fn process_interrupted_request(interrupted: CardInterrupted, batch: bool) -> String {
+    if batch {
+        serde_json::json!({
+            "payloads": Vec::<String>::new(),
+            "interrupted": interrupted.as_str(),
+        })
+    } else {
+        serde_json::json!({
+            "payload": "",
+            "interrupted": interrupted.as_str(),
+        })
+    }
+    .to_string()
+}
+
+/// Publishes the error answer to the pending request and disconnects. The event loop is
+/// driven until the DISCONNECT is sent, so the server gets the answer before the task ends.
+async fn answer_error_and_disconnect(
+    mqtt_client: &AsyncClient,
+    eventloop: &mut EventLoop,
+    topic_ack: String,
+    qos: QoS,
+    error: &str,
+) {
+    if let Err(e) = mqtt_client
+        .publish(topic_ack, qos, false, process_error_request(error))
+        .await
+    {
+        log::error!("Failed to publish the error answer: {:?}", e);
+    }
+    let _ = mqtt_client.disconnect().await;
+    let _ = tokio::time::timeout(Duration::from_secs(2), async {
+        while eventloop.poll().await.is_ok() {}
+    })
+    .await;
+}
+
+/// Builds the answer to the request received while the card is paused: every APDU is
+/// answered with `PAUSED_RESPONSE` and the answer is marked as paused. The finish is not
+/// paused, see `ensure_connection`.
+fn process_paused_request(request: &AuthRequest) -> String {
+    match &request.payloads {
+        Some(apdus) => serde_json::json!({
+            "payloads": vec![PAUSED_RESPONSE; apdus.len()],
+            "paused": true,
+        }),
+        None => serde_json::json!({
+            "payload": PAUSED_RESPONSE,
+            "paused": true,
+        }),
+    }
+    .to_string()
+}
+
+/// Builds the answer to the "payloads" request, the responses are in the order of the APDUs.
+fn process_rapdu_mqtt_hex_batch(rapdu_mqtt_hex: Vec<String>) -> String {
+    serde_json::json!({
+        "payloads": rapdu_mqtt_hex,
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pcsc::Protocols;
+    use std::ffi::CString;
+    use std::time::Instant;
+
+    /// Task pool entry of a stub card, the MQTT client is never connected.
+    fn stub_processing_card(
+        card_number: &str,
+        task_handle: JoinHandle<()>,
+        stop: Arc<Notify>,
+    ) -> ProcessingCard {
+        let reader_name = CString::new("Test Reader").unwrap();
+        let (mqtt_client, _eventloop) =
+            AsyncClient::new(MqttOptions::new(card_number, "localhost", 1883), 10);
+
+        ProcessingCard {
+            client_id: card_number.to_string(),
+            reader_name: Some("Test Reader".to_string()),
+            atr: None,
+            mqtt_client,
+            task_handle,
+            managed_card: Some(ManagedCard::stub(
+                &reader_name,
+                Protocols::T1,
+                String::new(),
+            )),
+            online: Default::default(),
+            metrics: Default::default(),
+            auth_process: Default::default(),
+            stop,
+            paused: Default::default(),
+            release_exchange: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn same_card_in_a_second_reader_is_not_connected() {
+        // Card number of this test only, the task pool is shared by the tests
+        let card_number = "duplicate-iccid-test";
+        let iccid = "8931000000000000001";
+
+        let first_card = stub_processing_card(
+            card_number,
+            async_runtime::spawn(async {}),
+            Arc::new(Notify::new()),
+        );
+        TASK_POOL.lock().await.push(first_card);
+
+        let second_reader = CString::new("Second Reader").unwrap();
+        let connected = ensure_connection(
+            &second_reader,
+            card_number.to_string(),
+            "3B9F96801FC78031E073FE211B63".to_string(),
+            iccid.to_string(),
+            ManagedCard::stub(&second_reader, Protocols::T1, iccid.to_string()),
+        )
+        .await;
+
+        let readers: Vec<Option<String>> = {
+            let mut task_pool = TASK_POOL.lock().await;
+            let readers = task_pool
+                .iter()
+                .filter(|card| card.client_id == card_number)
+                .map(|card| card.reader_name.clone())
+                .collect();
+            task_pool.retain(|card| card.client_id != card_number);
+            readers
+        };
+
+        assert!(!connected);
+        assert_eq!(readers, [Some("Test Reader".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn backoff_is_interrupted_by_the_stop() {
+        let stop = Notify::new();
+        stop.notify_one();
+
+        let started = Instant::now();
+        assert!(backoff_or_stop(&stop, Duration::from_secs(60)).await);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn backoff_ends_without_the_stop() {
+        let stop = Notify::new();
+        assert!(!backoff_or_stop(&stop, Duration::from_millis(10)).await);
+    }
+
+    #[tokio::test]
+    async fn removal_during_backoff_stops_the_task_before_the_grace_period() {
+        let stop = Arc::new(Notify::new());
+        let task_stop = Arc::clone(&stop);
+        let task_handle = async_runtime::spawn(async move {
+            while !backoff_or_stop(&task_stop, Duration::from_secs(60)).await {}
+        });
+
+        // An aborted task would only be noticed after the whole grace period
+        let started = Instant::now();
+        stop_tasks(vec![stub_processing_card("1", task_handle, stop)]).await;
+        assert!(started.elapsed() < get_smart_card_config().shutdown_grace());
+    }
+
+    #[test]
+    fn interruption_is_answered_out_of_band() {
+        let removed: serde_json::Value = serde_json::from_str(&process_interrupted_request(
+            CardInterrupted { reset: false },
+            false,
+        ))
+        .unwrap();
+        assert_eq!(
+            removed,
+            serde_json::json!({ "payload": "", "interrupted": "removed" })
+        );
+
+        let reset: serde_json::Value = serde_json::from_str(&process_interrupted_request(
+            CardInterrupted { reset: true },
+            true,
+        ))
+        .unwrap();
+        assert_eq!(
+            reset,
+            serde_json::json!({ "payloads": [], "interrupted": "reset" })
+        );
+    }
+
+    #[test]
+    fn response_topic_changes_only_the_last_level() {
+        assert_eq!(
+            response_topic("tacho-bridge/1000/request"),
+            "tacho-bridge/1000/response"
+        );
+        assert_eq!(
+            response_topic("fleet/request-a/1000/request"),
+            "fleet/request-a/1000/response"
+        );
+    }
+}
@@ -1,15 +1,20 @@
 // ───── Std Lib ─────
+use std::collections::HashMap;
 use std::error::Error;
 use std::error::Error as StdError;
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
 use std::mem;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::sync::Mutex as StdMutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 // ───── Crates ─────
 use lazy_static::lazy_static;
 use log::{debug, error, info, warn};
 use once_cell::sync::OnceCell;
 use rumqttc::v5::AsyncClient;
+use tokio::sync::{MutexGuard, Notify, OwnedMutexGuard, Semaphore};
 use tokio::time::Duration;
 
 use tauri::async_runtime::{JoinHandle, Mutex};
@@ -19,23 +24,81 @@ use pcsc::*;
 use pcsc::{Card, Protocols, State as PcscState};
 
 // ───── Local Modules ─────
-use crate::config::{get_from_cache, CacheSection};
-use crate::global_app_handle::emit_event;
-use crate::mqtt::{ensure_connection, remove_connections_all};
+use crate::config::{
+    get_cards_from_cache, get_config_path, get_from_cache, get_smart_card_config,
+    update_maintenance_config, CacheSection, CardConfig, CardNumberSource, PcscScope,
+    UnregisteredCardPolicy,
+};
+use crate::global_app_handle::{
+    emit_apdu_event, emit_card_error_event, emit_card_paused_event, emit_event,
+    emit_maintenance_event, emit_notification_event, emit_pin_tries_event,
+    emit_reader_changed_event, emit_unregistered_card_event, ApduPayload, NotificationPayload,
+    TachoState, UnregisteredCardPayload,
+};
+use crate::logger::trace_apdu;
+use crate::mqtt::{
+    ensure_connection, remove_card_connections, remove_connections, remove_connections_all,
+};
 
 // ───── Constants ─────
 const MAX_BUFFER_SIZE: usize = 260; // Example buffer size for smart card communication.
+const MAINTENANCE_RESPONSE: &str = "6985"; // "Conditions of use not satisfied", returned in the maintenance mode.
+const CARD_LOCK_TIMEOUT: Duration = Duration::from_secs(10); // Overall deadline to acquire the card lock before giving up.
+const CARD_LOCK_WARN_THRESHOLD: Duration = Duration::from_secs(2); // Lock waits longer than this are reported as contention.
+const DEBOUNCE_MAX_INTERVALS: u32 = 10; // Upper bound of the debounce wait, in debounce intervals.
+const CARD_LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(20); // Pause between two lock attempts.
+const MAX_GET_RESPONSE_CHAIN: usize = 32; // Upper bound of GET RESPONSE commands for a single T=0 APDU.
+const SHARING_VIOLATION_RETRIES: u32 = 4; // Connect retries while the card is used by another application.
+const SHARING_VIOLATION_BACKOFF: Duration = Duration::from_millis(50); // First pause between the retries, doubled every time.
+const MONITOR_WAIT_TICK: Duration = Duration::from_secs(10); // Longest wait for a status change while the watchdog is on.
+const WATCHDOG_CHECK_INTERVAL: Duration = Duration::from_secs(5); // Period of the monitor heartbeat checks.
+const MAX_BENCHMARK_ITERATIONS: u32 = 10_000; // Upper bound of benchmark_reader, keeps the reader from being tied up.
+const MAX_BENCHMARK_DURATION: Duration = Duration::from_secs(3); // The exchange is held at most this long, the card task waits for it.
+const EXCHANGE_WAIT_TIMEOUT: Duration = Duration::from_secs(5); // Longest wait of a command for the exchange in progress.
+const READ_BINARY_CHUNK: usize = 0xFF; // Bytes requested by one READ BINARY, the most of a short APDU below 256.
+const DF_TACHOGRAPH: &[u8] = &[0xFF, 0x54, 0x41, 0x43, 0x48, 0x4F]; // Name of the Tachograph DF, "\xFFTACHO".
+const MAX_SHORT_OFFSET: usize = 0x7FFF; // Highest offset READ BINARY encodes in P1-P2, see `read_binary_apdu`.
 
 /// Represents a card currently being processed (i.e., connected and active).
 #[derive(Debug)]
 pub struct ProcessingCard {
-    pub client_id: String, // it is Card number. Uses as client_id for mqtt connection
+    pub client_id: String, // it is Card number (or the ident for the app connection). The card MQTT client_id is prefixed with the ident
     pub reader_name: Option<String>, // Name of the smart card reader (e.g., "Alcor Micro AU9540 00 00").
     pub atr: Option<String>,         // ATR of the inserted card (hex-encoded).
     #[allow(dead_code)]
     // to say the compiler does not warn about an unused field that is used in another file.
     pub mqtt_client: AsyncClient, // MQTT client instance.
     pub task_handle: JoinHandle<()>, // Async task handle managing communication for this card.
+    pub managed_card: Option<ManagedCard>, // Card handle shared with the task. None for the app connection.
+    pub online: Arc<AtomicBool>,           // Connection status to the server, updated by the task.
+    pub metrics: Arc<StdMutex<ConnectionMetrics>>, // Reconnects and uptime, updated by the task.
+    pub auth_process: Arc<AtomicBool>,     // Authentication is in progress, updated by the task.
+    pub stop: Arc<Notify>, // Asks the task to stop at its next safe point, see `remove_connections`.
+    pub paused: Arc<AtomicBool>, // The requests of the server are not sent to the card, see `pause_card`.
+    pub release_exchange: Arc<Notify>, // Asks the task to drop the exchange of the reset authentication.
+}
+
+/// Broker connection statistics of a task pool entry, dropped together with the entry.
+#[derive(Debug, Default)]
+pub struct ConnectionMetrics {
+    pub reconnects: u32, // Connections established after the first one.
+    pub errors: u32,     // Errors of the event loop, each one is followed by a retry.
+    pub last_connected: Option<SystemTime>, // Time of the last ConnAck.
+}
+
+impl ConnectionMetrics {
+    /// Records the ConnAck from the broker.
+    pub fn record_connected(&mut self) {
+        if self.last_connected.is_some() {
+            self.reconnects += 1;
+        }
+        self.last_connected = Some(SystemTime::now());
+    }
+
+    /// Records an error of the event loop.
+    pub fn record_error(&mut self) {
+        self.errors += 1;
+    }
 }
 
 // ───── Statics ─────
@@ -43,23 +106,140 @@ lazy_static! {
     /// Global list of cards currently being processed (i.e., connected and active).
     pub static ref TASK_POOL: Arc<Mutex<Vec<ProcessingCard>>> =
         Arc::new(Mutex::new(Vec::new()));
+
+    /// Limits the number of simultaneous card I/O operations, so many cards authenticating
+    /// at once do not saturate the blocking thread pool.
+    /// The limit is read from the configuration on first use, a change requires a restart.
+    static ref CARD_IO_SEMAPHORE: Semaphore = {
+        let permits = get_smart_card_config().max_concurrent_io();
+        log::info!("Card I/O concurrency limit: {}", permits);
+        Semaphore::new(permits)
+    };
+
+    /// Context of the running `sc_monitor`, used to interrupt its blocking wait.
+    static ref MONITOR_CONTEXT: StdMutex<Option<Context>> = StdMutex::new(None);
+
+    /// Last reader of every card number, used to report a card moved to another reader.
+    static ref CARD_READERS: StdMutex<HashMap<String, String>> = StdMutex::new(HashMap::new());
+
+    /// Last ATR of every card number, used to connect the card without the reader, see `connect_card`.
+    static ref CARD_ATRS: StdMutex<HashMap<String, String>> = StdMutex::new(HashMap::new());
+
+    /// Last failure of every card number, kept after the card task ends, see `record_card_error`.
+    static ref CARD_LAST_ERRORS: StdMutex<HashMap<String, CardLastError>> =
+        StdMutex::new(HashMap::new());
 }
 
+/// Set when the reader states must be re-read from scratch, see `invalidate_reader_states`.
+static READER_STATES_INVALIDATED: AtomicBool = AtomicBool::new(false);
+
+/// Time of the last `sc_monitor` loop iteration, in milliseconds since the UNIX epoch. 0 before the start.
+static MONITOR_HEARTBEAT: AtomicU64 = AtomicU64::new(0);
+
+/// Set by `sc_watchdog` when the monitor is stalled, the monitor re-establishes the context.
+static MONITOR_RESTART: AtomicBool = AtomicBool::new(false);
+
 /// Represents errors that can occur while interacting with smart card readers.
+/// The common PC/SC errors have dedicated variants, so callers can react to them.
 #[derive(Debug)] // Enables use of `{:?}` for logging and debugging
 pub enum SmartCardError {
     /// Error indicating that the specified reader is no longer available or not recognized.
     UnknownReader,
 
+    /// The reader is temporarily unavailable (e.g. it is being disconnected).
+    ReaderUnavailable,
+
+    /// The card has been removed from the reader.
+    RemovedCard,
+
+    /// The card has been reset, the connection must be re-established.
+    ResetCard,
+
+    /// The card is used exclusively by another application.
+    SharingViolation,
+
+    /// There is no card in the reader.
+    NoSmartcard,
+
+    /// The card is present but not powered or not responding.
+    UnresponsiveCard,
+
+    /// The PC/SC service is not running or has been stopped.
+    NoService,
+
+    /// The blocking operation has been cancelled.
+    Cancelled,
+
     /// A catch-all for other types of errors, represented as a string message.
     Other(String),
 }
 
+impl SmartCardError {
+    /// Returns true if the card is not in the reader anymore, so a retry makes no sense.
+    pub fn is_card_gone(&self) -> bool {
+        matches!(
+            self,
+            SmartCardError::RemovedCard | SmartCardError::NoSmartcard
+        )
+    }
+
+    /// Returns true if the card was removed or reset in the middle of the exchange.
+    /// The card session of the server is lost either way.
+    pub fn is_card_interrupted(&self) -> bool {
+        self.is_card_gone() || matches!(self, SmartCardError::ResetCard)
+    }
+}
+
+/// Why the APDU could not be transmitted, see `ManagedCard::transmit_with_recreate`.
+enum TransmitFailure {
+    Interrupted { reset: bool }, // The card was removed, or reset if `reset` is set.
+    Failed(String), // Any other failure with its message, counted by the circuit breaker.
+}
+
+/// The card was removed or reset in the middle of the exchange, the server abandons the
+/// authentication. It is answered out of band, see `ManagedCard::send_apdu`: no status
+/// word means this unambiguously.
+#[derive(Debug, Clone, Copy)]
+pub struct CardInterrupted {
+    pub reset: bool, // The card was reset and is still present, otherwise it was removed.
+}
+
+impl CardInterrupted {
+    /// Name of the interruption in the answer to the server.
+    pub fn as_str(&self) -> &'static str {
+        if self.reset {
+            "reset"
+        } else {
+            "removed"
+        }
+    }
+}
+
+impl TransmitFailure {
+    /// Classifies the transmit error, the removed or reset card is not a card fault.
+    fn from_error(err: &(dyn StdError + Send + Sync + 'static)) -> Self {
+        match err.downcast_ref::<SmartCardError>() {
+            Some(card_err) if card_err.is_card_interrupted() => TransmitFailure::Interrupted {
+                reset: matches!(card_err, SmartCardError::ResetCard),
+            },
+            _ => TransmitFailure::Failed(err.to_string()),
+        }
+    }
+}
+
 impl std::fmt::Display for SmartCardError {
     /// Provides a user-friendly string representation of the error.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             SmartCardError::UnknownReader => write!(f, "UnknownReader"),
+            SmartCardError::ReaderUnavailable => write!(f, "ReaderUnavailable"),
+            SmartCardError::RemovedCard => write!(f, "RemovedCard"),
+            SmartCardError::ResetCard => write!(f, "ResetCard"),
+            SmartCardError::SharingViolation => write!(f, "SharingViolation"),
+            SmartCardError::NoSmartcard => write!(f, "NoSmartcard"),
+            SmartCardError::UnresponsiveCard => write!(f, "UnresponsiveCard"),
+            SmartCardError::NoService => write!(f, "NoService"),
+            SmartCardError::Cancelled => write!(f, "Cancelled"),
             SmartCardError::Other(s) => write!(f, "Other: {}", s),
         }
     }
@@ -73,13 +253,22 @@ impl std::error::Error for SmartCardError {
 impl From<pcsc::Error> for SmartCardError {
     /// Converts a `pcsc::Error` into a `SmartCardError`.
     ///
-    /// Attempts to classify `UnknownReader` errors specifically,
+    /// The common errors are mapped to the dedicated variants,
     /// all other errors are wrapped in `SmartCardError::Other`.
     fn from(err: pcsc::Error) -> Self {
-        if err.to_string().contains("UnknownReader") {
-            SmartCardError::UnknownReader
-        } else {
-            SmartCardError::Other(err.to_string())
+        match err {
+            pcsc::Error::UnknownReader => SmartCardError::UnknownReader,
+            pcsc::Error::ReaderUnavailable => SmartCardError::ReaderUnavailable,
+            pcsc::Error::RemovedCard => SmartCardError::RemovedCard,
+            pcsc::Error::ResetCard => SmartCardError::ResetCard,
+            pcsc::Error::SharingViolation => SmartCardError::SharingViolation,
+            pcsc::Error::NoSmartcard => SmartCardError::NoSmartcard,
+            pcsc::Error::UnpoweredCard | pcsc::Error::UnresponsiveCard => {
+                SmartCardError::UnresponsiveCard
+            }
+            pcsc::Error::NoService | pcsc::Error::ServiceStopped => SmartCardError::NoService,
+            pcsc::Error::Cancelled => SmartCardError::Cancelled,
+            err => SmartCardError::Other(err.to_string()),
         }
     }
 }
@@ -135,9 +324,11 @@ async fn process_reader_states(reader_states: &mut [ReaderState]) -> Result<(),
                 continue; // Skipping virtual reader processing
             }
 
-            // convert reader name to string
-            let reader_name = rs.name(); // .to_str().unwrap(); // convert reader name(&CStr) to string
-            let reader_name_string = reader_name.to_str().unwrap();
+            // convert reader name to string. Localized Windows readers may have non-UTF-8 names,
+            // those bytes are replaced instead of panicking the monitor loop.
+            let reader_name = rs.name();
+            let reader_name_lossy = reader_name.to_string_lossy();
+            let reader_name_string: &str = &reader_name_lossy;
 
             // convert ATR to hex string value
             let atr = hex::encode(rs.atr());
@@ -159,31 +350,97 @@ async fn process_reader_states(reader_states: &mut [ReaderState]) -> Result<(),
             // Default card_number var
             let mut card_number: String = String::new();
             let mut iccid: String = String::new();
+            let mut generation: Option<CardGeneration> = None;
 
             // Mechanism that controls the process of adding to TASK_POOL
             let action = should_register_new_card(reader_name_string, &atr).await;
 
             match action {
+                CardProcessingResult::Create if get_smart_card_config().maintenance => {
+                    // Maintenance mode: the card is not touched until the mode is disabled
+                    log::info!(
+                        "Maintenance mode: card in reader {} is not processed",
+                        reader_name_string
+                    );
+                }
+                CardProcessingResult::Create if !get_smart_card_config().is_atr_allowed(&atr) => {
+                    // Not a tachograph card, e.g. a building access card in the same reader
+                    log::debug!(
+                        "Card in reader {} with ATR {} is not in the ATR prefixes. Skipping...",
+                        reader_name_string,
+                        atr
+                    );
+                }
                 CardProcessingResult::Create => {
+                    // Some readers report the card before it is electrically ready
+                    let insert_delay = get_smart_card_config().insert_delay(reader_name_string);
+                    if !insert_delay.is_zero() {
+                        log::debug!(
+                            "Waiting {:?} for the card in reader {} to power up",
+                            insert_delay,
+                            reader_name_string
+                        );
+                        tokio::time::sleep(insert_delay).await;
+                    }
+
                     // The card may not be created initially
-                    match ManagedCard::new(reader_name, protocol) {
-                        Ok(managed_card) => match managed_card.get_iccid().await {
+                    match ManagedCard::new(reader_name, protocol).await {
+                        Ok(managed_card) => match read_iccid_with_retries(&managed_card).await {
                             Ok(received_iccid) => {
                                 log::info!("ICCID: {}", received_iccid);
 
                                 iccid = received_iccid.clone();
-                                card_number = get_from_cache(CacheSection::Cards, &iccid);
+                                card_number = resolve_card_number(&managed_card, &iccid).await;
+                                generation = Some(managed_card.get_generation().await);
+
+                                if card_number.is_empty() {
+                                    report_unregistered_card(&iccid, reader_name_string, &atr);
+                                }
 
-                                ensure_connection(
+                                // The reader is only tracked for the card accepted for the connection,
+                                // a duplicate in a second reader must not take over the card
+                                if ensure_connection(
                                     rs.name(),
                                     card_number.clone(),
                                     atr.clone(),
+                                    iccid.clone(),
                                     managed_card,
                                 )
-                                .await;
+                                .await
+                                {
+                                    track_card_reader(&card_number, reader_name_string, &atr);
+                                }
                             }
                             Err(e) => {
                                 log::error!("Failed to get ICCID: {}", e);
+
+                                // The card is still connected if its ATR is mapped to a card number
+                                let fallback = get_smart_card_config().atr_card_number(&atr);
+                                notify_iccid_failure(reader_name_string, fallback.is_some());
+
+                                if let Some(mapped_card_number) = fallback {
+                                    log::warn!(
+                                        "Card in reader {} is connected by the ATR as {}",
+                                        reader_name_string,
+                                        mapped_card_number
+                                    );
+
+                                    card_number = mapped_card_number;
+                                    generation = Some(managed_card.get_generation().await);
+
+                                    // The ICCID is unknown, the card is identified by the ATR only
+                                    if ensure_connection(
+                                        rs.name(),
+                                        card_number.clone(),
+                                        atr.clone(),
+                                        String::new(),
+                                        managed_card,
+                                    )
+                                    .await
+                                    {
+                                        track_card_reader(&card_number, reader_name_string, &atr);
+                                    }
+                                }
                             }
                         },
                         Err(e) => {
@@ -206,14 +463,23 @@ async fn process_reader_states(reader_states: &mut [ReaderState]) -> Result<(),
 
             // Emit event for Create or Delete, but not Ignore
             if action != CardProcessingResult::Ignore {
+                // ATR and protocol are reported whenever a card is present in the reader
+                let card_present = !atr.is_empty();
+
                 emit_event(
                     "global-cards-sync",
-                    iccid.into(),
-                    reader_name_string.into(),
-                    card_state_string.into(),
-                    card_number.clone().into(),
-                    None,
-                    None,
+                    TachoState {
+                        iccid,
+                        reader_name: reader_name_string.into(),
+                        card_state: card_state_string,
+                        card_number: card_number.clone(),
+                        online: None,
+                        authentication: None,
+                        generation,
+                        atr: card_present.then(|| atr.clone()),
+                        protocol: card_present.then(|| protocol_name(protocol)),
+                        errored: None,
+                    },
                 );
 
                 //  Trace status of the reader & card
@@ -232,6 +498,133 @@ async fn process_reader_states(reader_states: &mut [ReaderState]) -> Result<(),
     Ok(())
 }
 
+/// Reports a card whose ICCID is not mapped to a card number, as configured. Without it the
+/// card is just not connected and the user sees nothing.
+fn report_unregistered_card(iccid: &str, reader_name: &str, atr: &str) {
+    log::warn!(
+        "Card {} in reader {} has no card number, it is not connected",
+        iccid,
+        reader_name
+    );
+
+    if get_smart_card_config().unregistered_cards == UnregisteredCardPolicy::Notify {
+        let payload = UnregisteredCardPayload {
+            iccid: iccid.to_string(),
+            reader_name: reader_name.to_string(),
+            atr: atr.to_string(),
+        };
+        emit_unregistered_card_event("global-unregistered-card", payload);
+    }
+}
+
+/// Reads the ICCID, retrying as configured. Some cards and readers need a few attempts
+/// right after the insertion.
+async fn read_iccid_with_retries(
+    managed_card: &ManagedCard,
+) -> Result<String, Box<dyn StdError + Send + Sync>> {
+    let config = get_smart_card_config();
+    let mut attempt = 0;
+
+    loop {
+        match managed_card.get_iccid().await {
+            Ok(iccid) => return Ok(iccid),
+            Err(e) if attempt < config.iccid_retries() => {
+                attempt += 1;
+                log::warn!(
+                    "Failed to get ICCID: {}. Retrying ({}/{})...",
+                    e,
+                    attempt,
+                    config.iccid_retries()
+                );
+                tokio::time::sleep(config.iccid_retry_delay()).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Tells the user that the ICCID of the inserted card could not be read.
+fn notify_iccid_failure(reader_name: &str, connected_by_atr: bool) {
+    let message = if connected_by_atr {
+        format!(
+            "The card in reader {} could not be identified. It is connected by its ATR.",
+            reader_name
+        )
+    } else {
+        format!(
+            "The card in reader {} could not be identified. Reinsert the card.",
+            reader_name
+        )
+    };
+
+    let payload = NotificationPayload {
+        notification_type: "card".to_string(),
+        message,
+    };
+    emit_notification_event("global-notification", payload);
+}
+
+/// Returns the card number of the card according to the configured source.
+/// An empty string means the card number is unknown and the card is not connected.
+async fn resolve_card_number(managed_card: &ManagedCard, iccid: &str) -> String {
+    let config = get_smart_card_config();
+    if config.company_card_pool() {
+        return resolve_pool_card_number(managed_card, iccid).await;
+    }
+
+    let source = config.card_number_source;
+
+    if source != CardNumberSource::ReadFromCard {
+        let card_number = get_from_cache(CacheSection::Cards, iccid);
+        if !card_number.is_empty() || source == CardNumberSource::IccidMap {
+            return card_number;
+        }
+    }
+
+    match managed_card.read_card_number().await {
+        Ok(card_number) => card_number,
+        Err(e) => {
+            log::error!("Failed to read card number from card {}: {}", iccid, e);
+            String::new()
+        }
+    }
+}
+
+/// Returns the card number read from the card in the company card pool mode.
+/// Only company cards are connected, the configured cards are not consulted.
+async fn resolve_pool_card_number(managed_card: &ManagedCard, iccid: &str) -> String {
+    match managed_card.read_card_type().await {
+        Ok(TachographCardType::Company) => {}
+        Ok(card_type) => {
+            log::warn!(
+                "Card {} is a {:?} card, only company cards are connected from the pool",
+                iccid,
+                card_type
+            );
+            return String::new();
+        }
+        Err(e) => {
+            log::error!("Failed to read card type from card {}: {}", iccid, e);
+            return String::new();
+        }
+    }
+
+    match managed_card.read_card_number().await {
+        Ok(card_number) => {
+            log::info!(
+                "Company card {} is connected from the pool as {}",
+                iccid,
+                card_number
+            );
+            card_number
+        }
+        Err(e) => {
+            log::error!("Failed to read card number from card {}: {}", iccid, e);
+            String::new()
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum CardProcessingResult {
     Create,
@@ -286,6 +679,14 @@ pub async fn should_register_new_card(reader_name: &str, atr: &str) -> CardProce
         if let Some(index) = to_remove {
             let removed = pool.remove(index);
             removed.task_handle.abort();
+
+            // Remember where the card was, so its next insertion can be reported as a move
+            if removed.managed_card.is_some() {
+                CARD_READERS
+                    .lock()
+                    .unwrap()
+                    .insert(removed.client_id.clone(), reader_name.to_string());
+            }
             log::debug!("Case 2_3");
             log::warn!(
                 "Removed stale ProcessingCard for reader {} with old ATR {}",
@@ -301,6 +702,145 @@ pub async fn should_register_new_card(reader_name: &str, atr: &str) -> CardProce
     CardProcessingResult::Ignore
 }
 
+/// Records the reader and the ATR of the card and reports when the card has moved to another reader.
+fn track_card_reader(card_number: &str, reader_name: &str, atr: &str) {
+    if card_number.is_empty() {
+        return;
+    }
+
+    CARD_ATRS
+        .lock()
+        .unwrap()
+        .insert(card_number.to_string(), atr.to_string());
+
+    let old_reader = CARD_READERS
+        .lock()
+        .unwrap()
+        .insert(card_number.to_string(), reader_name.to_string());
+
+    if let Some(old_reader) = old_reader.filter(|old_reader| old_reader != reader_name) {
+        log::info!(
+            "{} | Card has moved from reader {} to reader {}",
+            card_number,
+            old_reader,
+            reader_name
+        );
+        emit_reader_changed_event(
+            "global-card-reader-changed",
+            card_number.to_string(),
+            old_reader,
+            reader_name.to_string(),
+        );
+    }
+}
+
+/// Last state change of every reader, used to coalesce the quick insert/remove bounces.
+#[derive(Debug, Default)]
+struct ReaderDebounce {
+    last_changes: HashMap<String, Instant>,
+}
+
+impl ReaderDebounce {
+    /// Records the change of the given readers.
+    fn record_changes(&mut self, readers: &[String], now: Instant) {
+        for name in readers {
+            if let Some(previous) = self.last_changes.insert(name.clone(), now) {
+                log::debug!("Reader {} changed again after {:?}", name, now - previous);
+            }
+        }
+    }
+
+    /// Waits until none of the `changed` readers has changed for the `interval`. Every further
+    /// change restarts the wait of its reader, so only the final state is processed. The overall
+    /// wait is bounded, so a reader that never settles can not block the processing forever.
+    ///
+    /// `wait_change` waits at most the given time and returns the readers changed meanwhile,
+    /// or `pcsc::Error::Timeout` without a change. Returns the number of further changes.
+    fn settle(
+        &mut self,
+        changed: Vec<String>,
+        interval: Duration,
+        mut wait_change: impl FnMut(Duration) -> Result<Vec<String>, pcsc::Error>,
+    ) -> Result<u32, pcsc::Error> {
+        let started = Instant::now();
+        self.record_changes(&changed, started);
+        if interval.is_zero() || changed.is_empty() {
+            return Ok(0);
+        }
+
+        let deadline = started + interval * DEBOUNCE_MAX_INTERVALS;
+        let mut settling = changed;
+        let mut bounces = 0;
+        loop {
+            let latest_change = settling
+                .iter()
+                .filter_map(|name| self.last_changes.get(name))
+                .max()
+                .copied()
+                .unwrap_or(started);
+            let stable_at = latest_change + interval;
+
+            let now = Instant::now();
+            if now >= stable_at {
+                return Ok(bounces);
+            }
+            if now >= deadline {
+                log::warn!(
+                    "Reader states are not stable after {:?}",
+                    interval * DEBOUNCE_MAX_INTERVALS
+                );
+                return Ok(bounces);
+            }
+
+            match wait_change(stable_at.min(deadline) - now) {
+                Ok(readers) => {
+                    bounces += 1;
+                    self.record_changes(&readers, Instant::now());
+                    for name in readers {
+                        if !settling.contains(&name) {
+                            settling.push(name);
+                        }
+                    }
+                }
+                Err(pcsc::Error::Timeout) => {} // No change, the stability is checked again
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Returns the names of the readers reporting a change.
+fn changed_readers(reader_states: &[ReaderState]) -> Vec<String> {
+    reader_states
+        .iter()
+        .filter(|rs| rs.event_state().contains(PcscState::CHANGED))
+        .map(|rs| rs.name().to_string_lossy().into_owned())
+        .collect()
+}
+
+/// Waits until the changed reader states are stable for the `interval`, see `ReaderDebounce`.
+fn settle_reader_states(
+    ctx: &Context,
+    reader_states: &mut [ReaderState],
+    interval: Duration,
+    debounce: &mut ReaderDebounce,
+) -> Result<(), pcsc::Error> {
+    let changed = changed_readers(reader_states);
+    debounce
+        .settle(changed, interval, |timeout| {
+            for rs in reader_states.iter_mut() {
+                rs.sync_current_state();
+            }
+            ctx.get_status_change(Some(timeout), reader_states)?;
+            Ok(changed_readers(reader_states))
+        })
+        .map(|bounces| {
+            if bounces > 0 {
+                log::debug!("Reader states settled after {} further changes", bounces);
+            }
+        })
+}
+
 /// Check if the reader is a virtual reader. This usually only applies to Windows.
 fn is_virtual_reader(reader_name: &CStr) -> bool {
     // Convert the reader name to a lowercase string
@@ -312,39 +852,262 @@ fn is_virtual_reader(reader_name: &CStr) -> bool {
         || reader_name_lower.contains("remote")
 }
 
+/// Forces `sc_monitor` to re-read the states of all readers immediately.
+/// Used when the configuration changes in a way the readers can not signal,
+/// e.g. a card is removed from the configuration while it stays inserted.
+pub fn invalidate_reader_states() {
+    READER_STATES_INVALIDATED.store(true, Ordering::SeqCst);
+
+    // Interrupt the blocking wait, the monitor picks the flag up right away
+    if let Some(ctx) = MONITOR_CONTEXT.lock().unwrap().as_ref() {
+        if let Err(e) = ctx.cancel() {
+            log::warn!("Failed to interrupt the reader monitor: {:?}", e);
+        }
+    }
+}
+
+/// Notifies the UI about the availability of the PC/SC service.
+fn notify_pcsc_service(message: &str) {
+    let payload = NotificationPayload {
+        notification_type: "pcsc".to_string(),
+        message: message.to_string(),
+    };
+    emit_notification_event("global-notification", payload);
+}
+
+/// Returns the notification for the failed context. A stopped service is the usual cause on
+/// Linux, where pcscd is not always enabled, so the user is told how to start it.
+fn pcsc_unavailable_message(error: &pcsc::Error) -> &'static str {
+    match error {
+        pcsc::Error::NoService | pcsc::Error::ServiceStopped if cfg!(target_os = "linux") => {
+            "PC/SC service (pcscd) appears to be stopped. Start it with: sudo systemctl enable --now pcscd"
+        }
+        pcsc::Error::NoService | pcsc::Error::ServiceStopped => {
+            "The smart card service appears to be stopped. Start the Smart Card service of the system."
+        }
+        _ => "The smart card service is unavailable. Retrying...",
+    }
+}
+
+/// Reports every known reader as unknown and closes the card connections, after the
+/// PC/SC context is lost. The cards are processed again once the context is re-established.
+async fn clear_reader_states(reader_states: &[ReaderState]) {
+    remove_card_connections().await;
+
+    for rs in reader_states
+        .iter()
+        .filter(|rs| rs.name() != PNP_NOTIFICATION())
+    {
+        let state = TachoState {
+            iccid: String::new(),
+            reader_name: rs.name().to_string_lossy().into_owned(),
+            card_state: format!("{:?}", PcscState::UNKNOWN),
+            card_number: String::new(),
+            online: None,
+            authentication: None,
+            generation: None,
+            atr: None,
+            protocol: None,
+            errored: None,
+        };
+        emit_event("global-cards-sync", state);
+    }
+}
+
+/// Processes the cards that are already inserted, without waiting for a state change.
+/// Some PC/SC stacks never report the cards present before the monitor is started.
+async fn scan_present_cards(
+    ctx: &Context,
+    readers_buf: &mut [u8],
+    reader_states: &mut Vec<ReaderState>,
+) -> Result<(), SmartCardError> {
+    setup_reader_states(ctx, readers_buf, reader_states)
+        .map_err(|e| SmartCardError::Other(e.to_string()))?;
+
+    // The new readers are UNAWARE, so the current states are returned right away
+    match ctx.get_status_change(Some(Duration::ZERO), &mut reader_states[..]) {
+        Ok(()) | Err(pcsc::Error::Timeout) => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    process_reader_states(reader_states).await
+}
+
+/// Returns the monotonic time in milliseconds since the first call, the heartbeat unit. At
+/// least 1, 0 is the heartbeat of a monitor that is not started. Unlike the wall clock it is
+/// not moved by the clock adjustments.
+fn monotonic_millis() -> u64 {
+    static EPOCH: OnceCell<Instant> = OnceCell::new();
+    EPOCH.get_or_init(Instant::now).elapsed().as_millis() as u64 + 1
+}
+
+/// Records that `sc_monitor` is alive, see `sc_watchdog`.
+fn touch_monitor_heartbeat() {
+    MONITOR_HEARTBEAT.store(monotonic_millis(), Ordering::Relaxed);
+}
+
+/// Supervises `sc_monitor`. If its heartbeat is older than the configured watchdog timeout,
+/// e.g. a driver bug blocks the status change wait, the monitor is told to re-establish the
+/// PC/SC context. The user is notified once per stall.
+pub async fn sc_watchdog() -> ! {
+    let mut stalled = false;
+
+    loop {
+        let slept_from = Instant::now();
+        tokio::time::sleep(WATCHDOG_CHECK_INTERVAL).await;
+
+        // A check much later than planned means a suspend of the machine, the monitor was
+        // suspended too and gets a full timeout to report again
+        if slept_from.elapsed() > WATCHDOG_CHECK_INTERVAL * 2 {
+            log::debug!("Watchdog check is late by {:?}", slept_from.elapsed());
+            touch_monitor_heartbeat();
+            continue;
+        }
+
+        let Some(timeout) = get_smart_card_config().watchdog_timeout() else {
+            continue;
+        };
+
+        let heartbeat = MONITOR_HEARTBEAT.load(Ordering::Relaxed);
+        if heartbeat == 0 {
+            continue; // The monitor is not started yet
+        }
+
+        let silence = Duration::from_millis(monotonic_millis().saturating_sub(heartbeat));
+        if silence <= timeout {
+            stalled = false;
+            continue;
+        }
+        if stalled {
+            continue; // Already signaled, the monitor did not recover yet
+        }
+        stalled = true;
+
+        log::error!(
+            "Smart card monitor is not responding for {:?}. Restarting it...",
+            silence
+        );
+        notify_pcsc_service("Card monitoring has stalled and is restarted.");
+
+        MONITOR_RESTART.store(true, Ordering::SeqCst);
+        if let Some(ctx) = MONITOR_CONTEXT.lock().unwrap().as_ref() {
+            if let Err(e) = ctx.cancel() {
+                log::warn!("Failed to interrupt the stalled reader monitor: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Establishes a PC/SC context in the configured scope, see `PcscScope`.
+pub fn establish_context() -> Result<Context, pcsc::Error> {
+    let scope = match get_smart_card_config().pcsc_scope {
+        PcscScope::User => Scope::User,
+        PcscScope::System => Scope::System,
+    };
+    Context::establish(scope)
+}
+
 // Automatically sync cards
 pub async fn sc_monitor() -> ! {
+    log::info!("PC/SC scope: {:?}", get_smart_card_config().pcsc_scope);
+
+    // Set while the PC/SC service is unavailable, so the UI is notified once per outage
+    let mut context_lost = false;
+
     loop {
+        touch_monitor_heartbeat();
         log::debug!("Starting the outer loop to establish context...");
-        let ctx = match Context::establish(Scope::User) {
+        let ctx = match establish_context() {
             Ok(ctx) => {
                 log::debug!("Successfully established context.");
+                if context_lost {
+                    log::info!("PC/SC context is re-established");
+                    notify_pcsc_service("Connection to the smart card service is restored.");
+                    context_lost = false;
+                }
                 ctx
             }
             Err(e) => {
-                log::error!(
-                    "Failed to establish context: {:?}. Retrying in 5 seconds...",
-                    e
-                );
+                // The outage is reported once, the retries are only traced
+                if !context_lost {
+                    log::error!(
+                        "Failed to establish context: {:?}. Retrying in 5 seconds...",
+                        e
+                    );
+                    notify_pcsc_service(pcsc_unavailable_message(&e));
+                    context_lost = true;
+                } else {
+                    log::debug!("Failed to establish context: {:?}. Retrying...", e);
+                }
                 tokio::time::sleep(std::time::Duration::from_secs(5)).await;
                 continue;
             }
         };
 
+        *MONITOR_CONTEXT.lock().unwrap() = Some(ctx.clone());
+
         let mut readers_buf = [0; 2048];
         let mut reader_states: Vec<ReaderState> = vec![
             // Listen for reader insertions/removals, if supported.
             ReaderState::new(PNP_NOTIFICATION(), PcscState::UNAWARE),
         ];
 
+        // Last state change of every reader, used for debouncing.
+        let mut debounce = ReaderDebounce::default();
+
+        // Power saving mode: the readers are re-listed only when the PnP pseudo-reader reports
+        // a change, see `SmartCardConfig::power_saving` for what it saves.
+        let power_saving = get_smart_card_config().power_saving();
+        let mut readers_changed = true; // The readers must be listed on the first pass.
+        log::info!("Reader monitor power saving mode: {}", power_saving);
+
         log::debug!("Initialized readers buffer and reader states.");
 
+        if get_smart_card_config().startup_scan() {
+            log::debug!("Scanning the cards present in the readers...");
+            if let Err(e) = scan_present_cards(&ctx, &mut readers_buf, &mut reader_states).await {
+                log::warn!("Failed to scan the present cards: {}", e);
+            }
+        }
+
         loop {
+            // With the watchdog on, the wait for a status change is bounded, so the heartbeat
+            // keeps going while nothing happens in the readers. Without it the wait is infinite.
+            let wait_timeout = get_smart_card_config()
+                .watchdog_timeout()
+                .map(|timeout| (timeout / 2).min(MONITOR_WAIT_TICK));
+
             log::debug!("Starting the inner loop to monitor reader states...");
-            if let Err(e) = setup_reader_states(&ctx, &mut readers_buf, &mut reader_states) {
-                log::error!("Failed to setup_reader_states: {:?}", e);
-                log::debug!("Exiting inner loop to re-establish context...");
-                break; // Exit the inner loop to re-establish context
+            touch_monitor_heartbeat();
+
+            if MONITOR_RESTART.swap(false, Ordering::SeqCst) {
+                log::warn!("Reader monitor is restarted by the watchdog");
+                context_lost = true;
+                break;
+            }
+
+            // Drop the known readers (the PNP pseudo-reader is the first), so they are re-added
+            // as UNAWARE and every card is processed again. The flag is cleared only once the
+            // states are re-read, see below
+            let invalidated = READER_STATES_INVALIDATED.load(Ordering::SeqCst);
+            if invalidated {
+                log::debug!("Reader states are invalidated. Re-reading all readers...");
+                reader_states.truncate(1);
+                readers_changed = true;
+            }
+
+            if !power_saving || readers_changed {
+                if let Err(e) = setup_reader_states(&ctx, &mut readers_buf, &mut reader_states) {
+                    log::error!("Failed to setup_reader_states: {:?}", e);
+                    log::debug!("Exiting inner loop to re-establish context...");
+                    context_lost = true;
+                    break; // Exit the inner loop to re-establish context
+                }
+            } else {
+                // The list of readers is unchanged, only the view of the state to wait on is updated
+                for rs in &mut reader_states {
+                    rs.sync_current_state();
+                }
             }
             log::debug!(
                 "Successfully set up reader states: {:?}",
@@ -354,19 +1117,56 @@ pub async fn sc_monitor() -> ! {
                     .collect::<Vec<_>>()
             );
 
-            if let Err(e) = ctx.get_status_change(None, &mut reader_states[..]) {
-                log::error!("get_status_change failed: {:?}", e);
+            // Invalidated after the check above: its cancel may have come before the wait
+            // and would be lost, so the states are re-read right away instead of blocking
+            if !invalidated && READER_STATES_INVALIDATED.load(Ordering::SeqCst) {
+                continue;
+            }
+
+            match ctx.get_status_change(wait_timeout, &mut reader_states[..]) {
+                Ok(()) => {
+                    // The states are re-read. An invalidation up to here is covered by the
+                    // processing below, a later one sets the flag again
+                    if invalidated {
+                        READER_STATES_INVALIDATED.store(false, Ordering::SeqCst);
+                    }
+                }
+                Err(pcsc::Error::Timeout) => {
+                    log::debug!("No status change within {:?}", wait_timeout);
+                    continue;
+                }
+                Err(pcsc::Error::Cancelled) => {
+                    log::debug!("Waiting for the status change is interrupted");
+                    continue;
+                }
+                Err(e) => {
+                    log::error!("get_status_change failed: {:?}", e);
+                    context_lost = true;
+                    break;
+                }
+            }
+
+            // The PnP pseudo-reader is the first in the list
+            readers_changed = reader_states[0].event_state().contains(PcscState::CHANGED);
+
+            // Coalesce quick insert/remove bounces before acting on the change
+            let debounce_interval = get_smart_card_config().debounce_interval();
+            if let Err(e) =
+                settle_reader_states(&ctx, &mut reader_states, debounce_interval, &mut debounce)
+            {
+                log::error!("Failed to settle reader states: {:?}", e);
+                context_lost = true;
                 break;
             }
 
             if let Err(e) = process_reader_states(&mut reader_states).await {
                 match e {
-                    SmartCardError::UnknownReader => {
-                        log::warn!("Detected UnknownReader. Sleeping 3s to avoid busy loop!");
+                    SmartCardError::UnknownReader | SmartCardError::ReaderUnavailable => {
+                        log::warn!("Detected {}. Sleeping 3s to avoid busy loop!", e);
                         tokio::time::sleep(Duration::from_secs(3)).await;
                     }
-                    SmartCardError::Other(msg) => {
-                        log::error!("SmartCard error: {}", msg);
+                    e => {
+                        log::error!("SmartCard error: {}", e);
                     }
                 }
 
@@ -374,13 +1174,32 @@ pub async fn sc_monitor() -> ! {
             }
 
             log::debug!("Waiting for the next status change...");
-            tokio::task::yield_now().await;
+            if !power_saving {
+                tokio::task::yield_now().await;
+            }
+        }
+
+        if context_lost {
+            log::warn!("PC/SC context is lost. Clearing the card states...");
+            notify_pcsc_service("Connection to the smart card service is lost. Reconnecting...");
+            clear_reader_states(&reader_states).await;
         }
 
         log::debug!("Re-establishing context...");
     }
 }
 
+/// Returns a human readable name of the protocol ("T0", "T1" or "Unknown").
+pub fn protocol_name(protocol: Protocols) -> String {
+    if protocol == Protocols::T0 {
+        "T0".to_string()
+    } else if protocol == Protocols::T1 {
+        "T1".to_string()
+    } else {
+        "Unknown".to_string()
+    }
+}
+
 /// Parses the ATR and extracts the communication protocol (T=0 or T=1).
 ///
 /// # Arguments
@@ -473,70 +1292,1450 @@ pub fn parse_atr_and_get_protocol(atr: &str) -> Protocols {
     Protocols::T0
 }
 
-// Manual card sync function. ////////////
-// This function is used to manually sync cards from anywhere in the program.
-// Manually sync cards. Clicking on the button in the frontend will trigger this function
-#[tauri::command]
-pub async fn manual_sync_cards(readername: String, restart: bool) -> Result<(), String> {
-    log::debug!("Manual sync cards function is called. Restart: {}", restart);
+/// Clock rate conversion factor Fi and the maximum clock in kHz, by the high nibble of TA1
+/// (ISO/IEC 7816-3, Table 7). None for the reserved values.
+const ATR_FI_TABLE: [Option<(u16, u32)>; 16] = [
+    Some((372, 4_000)),
+    Some((372, 5_000)),
+    Some((558, 6_000)),
+    Some((744, 8_000)),
+    Some((1116, 12_000)),
+    Some((1488, 16_000)),
+    Some((1860, 20_000)),
+    None,
+    None,
+    Some((512, 5_000)),
+    Some((768, 7_500)),
+    Some((1024, 10_000)),
+    Some((1536, 15_000)),
+    Some((2048, 20_000)),
+    None,
+    None,
+];
 
-    if restart {
-        // remove all connections
-        remove_connections_all().await;
+/// Baud rate adjustment factor Di by the low nibble of TA1 (ISO/IEC 7816-3, Table 8).
+const ATR_DI_TABLE: [Option<u16>; 16] = [
+    None,
+    Some(1),
+    Some(2),
+    Some(4),
+    Some(8),
+    Some(16),
+    Some(32),
+    Some(64),
+    Some(12),
+    Some(20),
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+];
 
-        return Ok(());
-    }
+/// What the ATR tells about the card, for the support.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AtrInfo {
+    pub atr: String,                // Normalized ATR hex.
+    pub protocol: String, // Protocol the app connects with, see `parse_atr_and_get_protocol`.
+    pub protocols: Vec<String>, // Protocols offered by the card, e.g. ["T0", "T1"].
+    pub dual_protocol: bool, // Both T=0 and T=1 are offered.
+    pub ta1: Option<String>, // TA1 hex, absent for the default speed.
+    pub fi: Option<u16>,  // Clock rate conversion factor from TA1.
+    pub di: Option<u16>,  // Baud rate adjustment factor from TA1.
+    pub max_baud_rate: Option<u32>, // Bits per second at the maximum clock, from TA1.
+    pub historical_bytes: String, // Historical bytes hex, often names the card OS.
+}
 
-    let ctx = Context::establish(Scope::User).expect("failed to establish context");
-    log::debug!("Context established successfully.");
+/// Decodes the ATR given as hex, e.g. copied from the log of a user.
+#[tauri::command]
+pub fn parse_atr(atr_hex: String) -> Result<AtrInfo, String> {
+    let atr = normalize_apdu_hex(&atr_hex).map_err(|e| format!("Invalid ATR: {}", e))?;
+    let bytes = hex::decode(&atr).map_err(|e| format!("Invalid ATR: {}", e))?;
 
-    let mut readers_buf = [0; 2048];
-    match ctx.list_readers(&mut readers_buf) {
-        Ok(readers) => {
-            if readers.count() == 0 {
-                log::warn!("No readers found. Exiting...");
-                return Ok(());
+    if bytes.len() < 2 {
+        return Err("Invalid ATR: TS and T0 are required".to_string());
+    }
+    if bytes[0] != 0x3B && bytes[0] != 0x3F {
+        return Err(format!(
+            "Invalid ATR: TS is {:02X}, 3B or 3F expected",
+            bytes[0]
+        ));
+    }
+
+    // Walk the interface bytes: every TDi announces the next group and a protocol
+    let truncated = || "Invalid ATR: the interface bytes are truncated".to_string();
+    let mut index = 2;
+    let mut presence = bytes[1] >> 4;
+    let mut first_group = true;
+    let mut ta1 = None;
+    let mut offered: Vec<u8> = Vec::new();
+    loop {
+        for bit in 0..3 {
+            if presence & (1 << bit) != 0 {
+                let byte = *bytes.get(index).ok_or_else(truncated)?;
+                if first_group && bit == 0 {
+                    ta1 = Some(byte);
+                }
+                index += 1;
+            }
+        }
+
+        if presence & 0x8 == 0 {
+            break;
+        }
+
+        let td = *bytes.get(index).ok_or_else(truncated)?;
+        index += 1;
+        // T=15 only carries the global interface bytes, it is not a protocol
+        if td & 0x0F != 0x0F && !offered.contains(&(td & 0x0F)) {
+            offered.push(td & 0x0F);
+        }
+        presence = td >> 4;
+        first_group = false;
+    }
+
+    // Without TD1 the card only supports T=0
+    if offered.is_empty() {
+        offered.push(0);
+    }
+
+    let historical_len = (bytes[1] & 0x0F) as usize;
+    let historical_end = (index + historical_len).min(bytes.len());
+
+    let fi_entry = ta1.and_then(|ta1| ATR_FI_TABLE[(ta1 >> 4) as usize]);
+    let di = ta1.and_then(|ta1| ATR_DI_TABLE[(ta1 & 0x0F) as usize]);
+
+    Ok(AtrInfo {
+        protocol: protocol_name(parse_atr_and_get_protocol(&atr)),
+        protocols: offered
+            .iter()
+            .map(|protocol| format!("T{}", protocol))
+            .collect(),
+        dual_protocol: offered.contains(&0) && offered.contains(&1),
+        ta1: ta1.map(|ta1| format!("{:02X}", ta1)),
+        fi: fi_entry.map(|(fi, _)| fi),
+        di,
+        max_baud_rate: fi_entry
+            .zip(di)
+            .map(|((fi, max_khz), di)| max_khz * 1000 * di as u32 / fi as u32),
+        historical_bytes: hex::encode_upper(&bytes[index.min(bytes.len())..historical_end]),
+        atr,
+    })
+}
+
+// Manual card sync function. ////////////
+// This function is used to manually sync cards from anywhere in the program.
+// Manually sync cards. Clicking on the button in the frontend will trigger this function
+#[tauri::command]
+pub async fn manual_sync_cards(readername: String, restart: bool) -> Result<(), String> {
+    log::debug!("Manual sync cards function is called. Restart: {}", restart);
+
+    if restart {
+        // remove all connections
+        remove_connections_all().await;
+
+        return Ok(());
+    }
+
+    // A PC/SC hiccup often clears with a fresh context, so the read is retried once
+    let mut readers_buf = [0; 2048];
+    let mut reader_states = match read_reader_states(&mut readers_buf) {
+        Ok(reader_states) => reader_states,
+        Err(e) => {
+            log::warn!("{}. Re-establishing the context and retrying...", e);
+            read_reader_states(&mut readers_buf).map_err(|e| {
+                log::error!("Manual sync failed: {}", e);
+                format!("Manual sync failed: {}", e)
+            })?
+        }
+    };
+
+    // Only the PnP notification is left without readers
+    if reader_states.len() <= 1 {
+        log::warn!("No readers found. Exiting...");
+        return Ok(());
+    }
+
+    process_reader_states(&mut reader_states)
+        .await
+        .map_err(|e| format!("Processing failed: {}", e))?;
+
+    Ok(())
+}
+
+/// Reads the current states of all readers in a new context, for `manual_sync_cards`.
+/// Without readers only the PnP notification state is returned.
+fn read_reader_states(readers_buf: &mut [u8]) -> Result<Vec<ReaderState>, String> {
+    let ctx = establish_context().map_err(|e| format!("Failed to establish context: {}", e))?;
+    log::debug!("Context established successfully.");
+
+    let mut reader_states = vec![
+        // Listen for reader insertions/removals, if supported.
+        ReaderState::new(PNP_NOTIFICATION(), State::UNAWARE),
+    ];
+
+    let reader_count = match ctx.list_readers(readers_buf) {
+        Ok(readers) => readers.count(),
+        Err(pcsc::Error::NoReadersAvailable) => 0,
+        Err(e) => return Err(format!("Failed to list readers: {}", e)),
+    };
+    if reader_count == 0 {
+        return Ok(reader_states);
+    }
+    log::debug!("Available readers found");
+
+    // setup readers states. Getting changes and other inits
+    setup_reader_states(&ctx, readers_buf, &mut reader_states)
+        .map_err(|e| format!("Failed to setup reader states: {}", e))?;
+
+    // The new readers are UNAWARE, so the current states are returned right away
+    match ctx.get_status_change(Some(Duration::from_secs(1)), &mut reader_states) {
+        Ok(()) | Err(pcsc::Error::Timeout) => Ok(reader_states),
+        Err(e) => Err(format!("Failed to get status change: {}", e)),
+    }
+}
+
+/// Enables or disables the maintenance mode.
+/// While enabled, the cards are not processed and all card connections are closed, so the
+/// readers can be serviced without closing the app. Disabling resumes the normal monitoring.
+#[tauri::command]
+pub async fn set_maintenance_mode(enabled: bool) -> Result<(), String> {
+    let config_path = get_config_path().map_err(|e| {
+        log::error!("Failed to get config path: {}", e);
+        format!("Failed to get config path: {}", e)
+    })?;
+
+    update_maintenance_config(&config_path, enabled).map_err(|e| {
+        log::error!("Failed to store maintenance mode: {}", e);
+        format!("Failed to store maintenance mode: {}", e)
+    })?;
+
+    if enabled {
+        remove_card_connections().await;
+    } else {
+        // The inserted cards do not produce a status change, so the readers are re-read
+        invalidate_reader_states();
+    }
+
+    emit_maintenance_event("global-maintenance-mode", enabled);
+    log::info!(
+        "Maintenance mode is {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+
+    Ok(())
+}
+
+/// Returns the data of each response, or an error if any of them has a status other than 9000.
+fn response_data(responses: &[String]) -> Result<Vec<Vec<u8>>, String> {
+    responses
+        .iter()
+        .map(|response| {
+            let data = response
+                .strip_suffix("9000")
+                .ok_or_else(|| format!("Card returned {}", response))?;
+            hex::decode(data).map_err(|e| format!("Failed to decode card response: {}", e))
+        })
+        .collect()
+}
+
+/// Decodes a `Name` of the card: the code page (ISO/IEC 8859 part number) and 35 bytes of text.
+/// An unknown code page falls back to Latin-1, the padding is trimmed.
+fn decode_name(name: &[u8]) -> String {
+    let Some((&code_page, text)) = name.split_first() else {
+        return String::new();
+    };
+
+    let encoding = encoding_rs::Encoding::for_label(format!("iso-8859-{}", code_page).as_bytes())
+        .unwrap_or(encoding_rs::WINDOWS_1252);
+    let (decoded, _) = encoding.decode_without_bom_handling(text);
+
+    decoded
+        .trim_matches(|c: char| c.is_whitespace() || c == '\0' || c == '\u{FF}')
+        .to_string()
+}
+
+/// Generation of the tachograph card.
+///
+/// Detected from the applications and elementary files available on the card
+/// (Annex 1C, Appendix 2): gen1 cards only have the `Tachograph` DF, gen2 cards
+/// additionally have the `Tachograph_G2` DF, and gen2 version 2 cards carry
+/// `EF Application_Identification_V2` inside it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub enum CardGeneration {
+    Gen1,
+    Gen2v1,
+    Gen2v2,
+    Unknown,
+}
+
+/// Type of the tachograph card, `typeOfTachographCardId` of `EF Application_Identification`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TachographCardType {
+    Driver,
+    Workshop,
+    Control,
+    Company,
+}
+
+/// Holder of the card as written on the card, for the operator to confirm the right card is in.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CardIdentity {
+    pub card_type: TachographCardType,
+    pub card_number: String, // Card number from the card, not from the config.
+    pub issuing_authority: String, // Authority that issued the card.
+    pub holder_name: Option<String>, // First names and surname. None for the company card.
+    pub organisation_name: Option<String>, // Company, workshop or control body. None for the driver card.
+    pub expiry_date: Option<String>,       // Card expiry, YYYY-MM-DD.
+}
+
+/// PIN try counter of the card, see `ManagedCard::read_pin_tries`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case", tag = "status", content = "remaining")]
+pub enum PinTries {
+    Remaining(u8), // Tries left before the PIN is blocked.
+    Verified,      // The PIN is already verified in this card session.
+    Blocked,       // No tries left, the card must be unblocked by the issuer.
+    Unknown,       // The card has no PIN or doesn't report the counter, e.g. a company card.
+}
+
+impl PinTries {
+    /// Interprets the status word of VERIFY without the PIN (ISO/IEC 7816-4).
+    fn from_status(status: &str) -> Self {
+        match status.to_uppercase().as_str() {
+            "9000" => PinTries::Verified,
+            "6983" | "63C0" => PinTries::Blocked,
+            status => match status.strip_prefix("63C") {
+                Some(tries) => {
+                    u8::from_str_radix(tries, 16).map_or(PinTries::Unknown, PinTries::Remaining)
+                }
+                None => PinTries::Unknown,
+            },
+        }
+    }
+}
+
+/// Reads the PIN try counter of the card inserted into the given reader, so the UI can warn
+/// before the card is blocked. Waits for the authentication in progress.
+#[tauri::command]
+pub async fn get_pin_tries(reader_name: String) -> Result<PinTries, String> {
+    let managed_card = get_managed_card(ReaderOrCard::Reader(reader_name.clone()))
+        .await
+        .ok_or_else(|| format!("No active card found for reader {}", reader_name))?;
+
+    let _exchange = managed_card.wait_exchange().await?;
+    let pin_tries = managed_card.read_pin_tries().await.map_err(|e| {
+        log::error!(
+            "Failed to read the PIN try counter for reader {}: {}",
+            reader_name,
+            e
+        );
+        format!("Failed to read the PIN try counter: {}", e)
+    })?;
+
+    if let Some(card_number) = TASK_POOL
+        .lock()
+        .await
+        .iter()
+        .find(|card| card.reader_name.as_deref() == Some(reader_name.as_str()))
+        .map(|card| card.client_id.clone())
+    {
+        emit_pin_tries_event("global-card-pin-tries", card_number, pin_tries);
+    }
+
+    Ok(pin_tries)
+}
+
+/// Key of an active card in the task pool, see `get_managed_card`.
+#[derive(Debug, Clone)]
+pub enum ReaderOrCard {
+    Reader(String), // Name of the reader the card is inserted into.
+    Card(String),   // Card number of the card.
+}
+
+impl ReaderOrCard {
+    /// Returns whether the task pool entry is the card task of this reader or card number.
+    /// The app connection has no card and never matches.
+    fn matches(&self, card: &ProcessingCard) -> bool {
+        card.managed_card.is_some()
+            && match self {
+                ReaderOrCard::Reader(reader_name) => {
+                    card.reader_name.as_deref() == Some(reader_name.as_str())
+                }
+                ReaderOrCard::Card(card_number) => card.client_id == *card_number,
+            }
+    }
+}
+
+/// Returns the handle of the active card, None if there is no card task. The handle is cloned
+/// out under the lock, so the task pool is not held during the card I/O.
+pub async fn get_managed_card(by: ReaderOrCard) -> Option<ManagedCard> {
+    let pool = TASK_POOL.lock().await;
+    pool.iter()
+        .find(|card| by.matches(card))
+        .and_then(|card| card.managed_card.clone())
+}
+
+/// Reads the holder identification of the card inserted into the given reader. Read-only, the
+/// card is not reset. Waits for the authentication in progress, the read changes the selected file.
+#[tauri::command]
+pub async fn get_card_identity(reader_name: String) -> Result<CardIdentity, String> {
+    log::debug!("Card identity is requested for reader: {}", reader_name);
+
+    let managed_card = get_managed_card(ReaderOrCard::Reader(reader_name.clone()))
+        .await
+        .ok_or_else(|| format!("No active card found for reader {}", reader_name))?;
+
+    let _exchange = managed_card.wait_exchange().await?;
+    managed_card.read_identity().await.map_err(|e| {
+        log::error!(
+            "Failed to read card identity for reader {}: {}",
+            reader_name,
+            e
+        );
+        format!("Failed to read card identity: {}", e)
+    })
+}
+
+/// Result of `benchmark_reader`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ReaderBenchmark {
+    pub reader_name: String,
+    pub iterations: u32, // Iterations done, fewer than requested when the duration limit is reached.
+    pub errors: u32,     // Failed transmits and responses other than 9000.
+    pub total_ms: u64,   // Time of all iterations.
+    pub average_latency_ms: f64,
+}
+
+/// Measures the APDU round trip of the reader with a harmless SELECT MF, to compare readers
+/// before a rollout. The card of a running task is used only if it is not authenticating.
+/// The benchmark stops after `MAX_BENCHMARK_DURATION`, the card task waits for the exchange.
+#[tauri::command]
+pub async fn benchmark_reader(
+    reader_name: String,
+    iterations: u32,
+) -> Result<ReaderBenchmark, String> {
+    log::debug!(
+        "Benchmark is called for reader: {}, iterations: {}",
+        reader_name,
+        iterations
+    );
+
+    if !(1..=MAX_BENCHMARK_ITERATIONS).contains(&iterations) {
+        return Err(format!(
+            "Iterations must be between 1 and {}",
+            MAX_BENCHMARK_ITERATIONS
+        ));
+    }
+
+    let managed_card = match get_managed_card(ReaderOrCard::Reader(reader_name.clone())).await {
+        Some(managed_card) if managed_card.is_stub() => {
+            return Err(format!("Card in reader {} is a stub", reader_name))
+        }
+        Some(managed_card) => managed_card,
+        None => {
+            let reader = CString::new(reader_name.as_str()).map_err(|e| e.to_string())?;
+            ManagedCard::new(&reader, Protocols::ANY)
+                .await
+                .map_err(|e| {
+                    log::error!(
+                        "Failed to connect the card in reader {}: {}",
+                        reader_name,
+                        e
+                    );
+                    format!("Failed to connect the card: {}", e)
+                })?
+        }
+    };
+
+    let _exchange = managed_card
+        .try_lock_exchange()
+        .ok_or_else(|| "The card is busy with the authentication, try again later".to_string())?;
+
+    let mut errors = 0;
+    let mut done = 0;
+    let started = Instant::now();
+    while done < iterations && started.elapsed() < MAX_BENCHMARK_DURATION {
+        done += 1;
+        // SELECT MF without the response data
+        match managed_card.apdu_transmit("00A4000C023F00").await {
+            Ok(response) if response.ends_with("9000") => {}
+            Ok(response) => {
+                log::debug!("Benchmark of reader {}: response {}", reader_name, response);
+                errors += 1;
+            }
+            Err(e) => {
+                log::debug!("Benchmark of reader {}: {}", reader_name, e);
+                errors += 1;
+            }
+        }
+    }
+    let total = started.elapsed();
+
+    if done < iterations {
+        log::info!(
+            "Benchmark of reader {} is stopped after {} of {} iterations",
+            reader_name,
+            done,
+            iterations
+        );
+    }
+
+    let benchmark = ReaderBenchmark {
+        reader_name,
+        iterations: done,
+        errors,
+        total_ms: total.as_millis() as u64,
+        average_latency_ms: total.as_secs_f64() * 1000.0 / done as f64,
+    };
+    log::info!("Reader benchmark: {:?}", benchmark);
+
+    Ok(benchmark)
+}
+
+/// Forces a re-read of the ICCID of the card inserted into the given reader.
+/// If the ICCID has changed, the card task is restarted so it is mapped to the right card number.
+#[tauri::command]
+pub async fn refresh_iccid(readername: String) -> Result<String, String> {
+    log::debug!("Refresh ICCID is called for reader: {}", readername);
+
+    let found = {
+        let by = ReaderOrCard::Reader(readername.clone());
+        let pool = TASK_POOL.lock().await;
+        pool.iter().find(|card| by.matches(card)).and_then(|card| {
+            card.managed_card
+                .clone()
+                .map(|managed_card| (card.client_id.clone(), managed_card))
+        })
+    };
+
+    let (client_id, managed_card) =
+        found.ok_or_else(|| format!("No active card found for reader {}", readername))?;
+
+    let exchange = managed_card.wait_exchange().await?;
+    let iccid = managed_card.refresh_iccid().await.map_err(|e| {
+        log::error!("Failed to refresh ICCID for reader {}: {}", readername, e);
+        format!("Failed to refresh ICCID: {}", e)
+    })?;
+
+    let card_number = resolve_card_number(&managed_card, &iccid).await;
+    drop(exchange);
+
+    if card_number != client_id {
+        log::warn!(
+            "Reader {}: ICCID {} maps to card {:?} instead of {}. Restarting the card task.",
+            readername,
+            iccid,
+            card_number,
+            client_id
+        );
+
+        remove_connections(vec![client_id]).await;
+        manual_sync_cards(readername, false).await?;
+    }
+
+    Ok(iccid)
+}
+
+/// Resets the card and the authentication state of an active card, e.g. when the server lost
+/// its session in the middle of the authentication. Same as the "finish" from the server.
+#[tauri::command]
+pub async fn reset_card_auth(card_number: String) -> Result<(), String> {
+    log::debug!(
+        "Reset of the authentication is called for card: {}",
+        card_number
+    );
+
+    let found = {
+        let by = ReaderOrCard::Card(card_number.clone());
+        let pool = TASK_POOL.lock().await;
+        pool.iter().find(|card| by.matches(card)).and_then(|card| {
+            card.managed_card.clone().map(|managed_card| {
+                (
+                    managed_card,
+                    Arc::clone(&card.auth_process),
+                    Arc::clone(&card.release_exchange),
+                    card.online.load(Ordering::Relaxed),
+                    card.atr.clone(),
+                )
+            })
+        })
+    };
+
+    let (managed_card, auth_process, release_exchange, online, atr) =
+        found.ok_or_else(|| format!("Card {} is not active", card_number))?;
+
+    // Reset the card to its original state
+    managed_card
+        .reconnect()
+        .await
+        .map_err(|e| format!("Failed to reset card {}: {}", card_number, e))?;
+    auth_process.store(false, Ordering::Relaxed);
+    // The task holds the exchange until the finish, which will not come anymore
+    release_exchange.notify_one();
+
+    let iccid = managed_card.get_iccid().await.unwrap_or_default();
+    emit_event(
+        "global-cards-sync",
+        TachoState {
+            iccid,
+            reader_name: managed_card.reader_name.to_string_lossy().into_owned(),
+            card_state: "PRESENT".into(),
+            card_number: card_number.clone(),
+            online: Some(online),
+            authentication: Some(false),
+            generation: Some(managed_card.get_generation().await),
+            atr,
+            protocol: Some(protocol_name(managed_card.protocol())),
+            errored: Some(managed_card.is_errored()),
+        },
+    );
+
+    log::info!("Authentication of the card {} is reset", card_number);
+
+    Ok(())
+}
+
+/// Connects the configured card to the broker without the physical card, to test the topic
+/// wiring on the server side. The last ATR, reader and ICCID of the card are used and every
+/// APDU is answered with the dry-run response. Refused if the card was not seen in this run.
+#[tauri::command]
+pub async fn connect_card(card_number: String) -> Result<(), String> {
+    log::debug!("Manual connection is called for card: {}", card_number);
+
+    let iccid = get_cards_from_cache()
+        .get(&card_number)
+        .map(|card| card.iccid.clone())
+        .filter(|iccid| !iccid.is_empty())
+        .ok_or_else(|| format!("Card {} is not configured", card_number))?;
+
+    let atr = CARD_ATRS.lock().unwrap().get(&card_number).cloned();
+    let reader_name = CARD_READERS.lock().unwrap().get(&card_number).cloned();
+    let (atr, reader_name) = atr.zip(reader_name).ok_or_else(|| {
+        format!(
+            "Card {} has no cached ATR. Insert the card once before connecting it.",
+            card_number
+        )
+    })?;
+
+    if TASK_POOL
+        .lock()
+        .await
+        .iter()
+        .any(|card| card.client_id == card_number)
+    {
+        return Err(format!("Card {} is already active", card_number));
+    }
+
+    let reader_name = CString::new(reader_name).map_err(|e| e.to_string())?;
+    let protocol = parse_atr_and_get_protocol(&atr);
+    let managed_card = ManagedCard::stub(&reader_name, protocol, iccid.clone());
+
+    log::warn!(
+        "{} | Card is connected without the physical card. APDUs get the dry-run response.",
+        card_number
+    );
+    if !ensure_connection(&reader_name, card_number.clone(), atr, iccid, managed_card).await {
+        return Err(format!(
+            "Card {} is not connected, it is disabled, refused or already active",
+            card_number
+        ));
+    }
+
+    Ok(())
+}
+
+/// Sets the paused flag of the connected card and reports it to the frontend.
+async fn set_card_paused(card_number: &str, paused: bool) -> Result<(), String> {
+    let by = ReaderOrCard::Card(card_number.to_string());
+    let pool = TASK_POOL.lock().await;
+    let card = pool
+        .iter()
+        .find(|card| by.matches(card))
+        .ok_or_else(|| format!("Card {} is not connected", card_number))?;
+
+    if card.paused.swap(paused, Ordering::Relaxed) != paused {
+        log::info!(
+            "{} | Broker traffic of the card is {}",
+            card_number,
+            if paused { "paused" } else { "resumed" }
+        );
+        emit_card_paused_event("global-card-paused", card_number.to_string(), paused);
+    }
+
+    Ok(())
+}
+
+/// Pauses the broker traffic of the card for troubleshooting. The connection stays up,
+/// the requests are answered as paused and no APDU is sent to the card. Only the finish
+/// is processed, so the authentication in progress releases and resets the card. The pause
+/// lasts until `resume_card` or until the card is connected again, e.g. after a reinsertion.
+#[tauri::command]
+pub async fn pause_card(card_number: String) -> Result<(), String> {
+    set_card_paused(&card_number, true).await
+}
+
+/// Resumes the broker traffic of the card paused by `pause_card`.
+#[tauri::command]
+pub async fn resume_card(card_number: String) -> Result<(), String> {
+    set_card_paused(&card_number, false).await
+}
+
+/// Removes the task pool entries of the reader whose removal was not detected, e.g. a reader
+/// unplugged in the middle of a status change. The tasks are aborted, their broker connections
+/// are closed and the cards are reported as removed. Returns the removed card numbers.
+#[tauri::command]
+pub async fn force_remove_reader(reader_name: String) -> Result<Vec<String>, String> {
+    log::debug!("Force removal is called for reader: {}", reader_name);
+
+    let removed: Vec<ProcessingCard> = {
+        let mut pool = TASK_POOL.lock().await;
+        let (removed, kept) = mem::take(&mut *pool)
+            .into_iter()
+            .partition(|card| card.reader_name.as_deref() == Some(reader_name.as_str()));
+        *pool = kept;
+        removed
+    };
+
+    if removed.is_empty() {
+        return Err(format!("No task found for reader {}", reader_name));
+    }
+
+    let mut card_numbers = Vec::with_capacity(removed.len());
+    for card in removed {
+        card.task_handle.abort();
+        // The event loop is aborted, the request may never reach the broker
+        if let Err(e) = card.mqtt_client.try_disconnect() {
+            log::debug!(
+                "{} Failed to disconnect from the broker: {}",
+                card.client_id,
+                e
+            );
+        }
+
+        log::warn!(
+            "TASK_POOL: Forced removal of client_id: {}, reader: {}, atr: {}",
+            card.client_id,
+            reader_name,
+            card.atr.as_deref().unwrap_or("unknown"),
+        );
+
+        let iccid = card
+            .managed_card
+            .as_ref()
+            .and_then(|managed_card| managed_card.iccid.lock().unwrap().clone())
+            .unwrap_or_default();
+        emit_event(
+            "global-cards-sync",
+            TachoState {
+                iccid,
+                reader_name: reader_name.clone(),
+                card_state: "State(REMOVED)".into(), // Parsed by the frontend like the PC/SC state.
+                card_number: card.client_id.clone(),
+                online: Some(false),
+                authentication: Some(false),
+                generation: None,
+                atr: None,
+                protocol: None,
+                errored: None,
+            },
+        );
+
+        card_numbers.push(card.client_id);
+    }
+
+    Ok(card_numbers)
+}
+
+/// Last failure of a card, shown to the user next to the card.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CardLastError {
+    pub message: String,
+    pub timestamp: String, // RFC 3339, local time.
+}
+
+/// Remembers the failure of the card and reports it to the frontend.
+pub fn record_card_error(card_number: &str, message: String) {
+    if card_number.is_empty() {
+        return;
+    }
+
+    let last_error = CardLastError {
+        message,
+        timestamp: chrono::Local::now().to_rfc3339(),
+    };
+    CARD_LAST_ERRORS
+        .lock()
+        .unwrap()
+        .insert(card_number.to_string(), last_error.clone());
+
+    emit_card_error_event(
+        "global-card-error",
+        card_number.to_string(),
+        Some(last_error),
+    );
+}
+
+/// Forgets the failure of the card after a successful operation.
+pub fn clear_card_error(card_number: &str) {
+    if CARD_LAST_ERRORS
+        .lock()
+        .unwrap()
+        .remove(card_number)
+        .is_some()
+    {
+        emit_card_error_event("global-card-error", card_number.to_string(), None);
+    }
+}
+
+/// Configured card combined with its live state.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CardSnapshot {
+    pub card_number: String,
+    pub config: CardConfig,
+    pub present: bool,                     // The card is inserted and processed.
+    pub online: bool,                      // The card is connected to the server.
+    pub reader_name: Option<String>,       // Reader holding the card, if present.
+    pub atr: Option<String>,               // ATR of the card, if present.
+    pub last_error: Option<CardLastError>, // Last failure, cleared by the next success.
+    pub paused: bool,                      // The broker traffic of the card is paused.
+}
+
+/// Returns all configured cards with their live state from the task pool.
+/// Gives the frontend a single authoritative call to populate the card list.
+#[tauri::command]
+pub async fn get_cards_snapshot() -> Vec<CardSnapshot> {
+    let cards = get_cards_from_cache();
+    let last_errors = CARD_LAST_ERRORS.lock().unwrap().clone();
+    let pool = TASK_POOL.lock().await;
+
+    let mut snapshot: Vec<CardSnapshot> = cards
+        .into_iter()
+        .map(|(card_number, config)| {
+            let processing = pool
+                .iter()
+                .find(|card| card.managed_card.is_some() && card.client_id == card_number);
+
+            CardSnapshot {
+                present: processing.is_some(),
+                online: processing
+                    .map(|card| card.online.load(Ordering::Relaxed))
+                    .unwrap_or(false),
+                reader_name: processing.and_then(|card| card.reader_name.clone()),
+                atr: processing.and_then(|card| card.atr.clone()),
+                last_error: last_errors.get(&card_number).cloned(),
+                paused: processing
+                    .map(|card| card.paused.load(Ordering::Relaxed))
+                    .unwrap_or(false),
+                card_number,
+                config,
+            }
+        })
+        .collect();
+
+    snapshot.sort_by(|a, b| a.card_number.cmp(&b.card_number));
+    snapshot
+}
+
+/// Entry of the task pool, reported to the support.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TaskPoolEntry {
+    pub client_id: String, // Card number, or the ident for the app connection.
+    pub reader_name: Option<String>, // Reader holding the card. None for the app connection.
+    pub atr: Option<String>, // ATR of the card. None for the app connection.
+    pub is_card: bool,     // False for the app connection.
+    pub online: bool,      // The task is connected to the server.
+    pub finished: bool,    // The task has ended, but the entry is still in the pool.
+}
+
+/// Returns the current contents of the task pool, what the backend thinks is connected.
+/// Read-only, the pool is locked only to copy the entries.
+#[tauri::command]
+pub async fn debug_task_pool() -> Vec<TaskPoolEntry> {
+    let pool = TASK_POOL.lock().await;
+
+    pool.iter()
+        .map(|card| TaskPoolEntry {
+            client_id: card.client_id.clone(),
+            reader_name: card.reader_name.clone(),
+            atr: card.atr.clone(),
+            is_card: card.managed_card.is_some(),
+            online: card.online.load(Ordering::Relaxed),
+            finished: card.task_handle.inner().is_finished(),
+        })
+        .collect()
+}
+
+/// Broker connection statistics of a card, reported by `connection_metrics`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CardConnectionMetrics {
+    pub card_number: String,
+    pub reader_name: Option<String>,
+    pub online: bool,
+    pub reconnects: u32,
+    pub errors: u32,
+    pub last_connected: Option<u64>, // Unix time in seconds.
+    pub uptime_secs: Option<u64>,    // Time since the last connect, while online.
+}
+
+/// Returns the reconnect counters and the uptime of every card connection.
+/// The statistics are reset when the card is removed from the task pool.
+#[tauri::command]
+pub async fn connection_metrics() -> Vec<CardConnectionMetrics> {
+    let pool = TASK_POOL.lock().await;
+
+    pool.iter()
+        .filter(|card| card.managed_card.is_some())
+        .map(|card| {
+            let metrics = card.metrics.lock().unwrap();
+            let online = card.online.load(Ordering::Relaxed);
+
+            CardConnectionMetrics {
+                card_number: card.client_id.clone(),
+                reader_name: card.reader_name.clone(),
+                online,
+                reconnects: metrics.reconnects,
+                errors: metrics.errors,
+                last_connected: metrics
+                    .last_connected
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|duration| duration.as_secs()),
+                uptime_secs: metrics
+                    .last_connected
+                    .filter(|_| online)
+                    .and_then(|time| time.elapsed().ok())
+                    .map(|duration| duration.as_secs()),
+            }
+        })
+        .collect()
+}
+
+/// Information about a smart card reader, reported to the support.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ReaderInfo {
+    pub name: String,                     // Name of the reader as reported by PC/SC.
+    pub vendor_name: Option<String>,      // SCARD_ATTR_VENDOR_NAME, if supported by the reader.
+    pub firmware_version: Option<String>, // SCARD_ATTR_VENDOR_IFD_VERSION, if supported by the reader.
+    pub is_virtual: bool,                 // Virtual readers are skipped by the card processing.
+}
+
+/// Queries vendor name and firmware version of the reader.
+/// Readers that don't support the attributes return None for them.
+/// The card is left as is on disconnect, a reset would break the exchange of a card task.
+fn get_reader_attributes(ctx: &Context, reader_name: &CStr) -> (Option<String>, Option<String>) {
+    // Direct mode works without a card on most readers, Shared mode needs a card inserted
+    let card = match ctx.connect(reader_name, ShareMode::Direct, Protocols::UNDEFINED) {
+        Ok(card) => card,
+        Err(_) => match ctx.connect(reader_name, ShareMode::Shared, Protocols::ANY) {
+            Ok(card) => card,
+            Err(e) => {
+                log::debug!(
+                    "Can't connect to reader {:?} to read attributes: {}",
+                    reader_name,
+                    e
+                );
+                return (None, None);
+            }
+        },
+    };
+
+    let vendor_name = card
+        .get_attribute_owned(Attribute::VendorName)
+        .ok()
+        .map(|bytes| {
+            String::from_utf8_lossy(&bytes)
+                .trim_end_matches('\0')
+                .trim()
+                .to_string()
+        })
+        .filter(|name| !name.is_empty());
+
+    // DWORD 0xMMmmbbbb: major version, minor version and build number
+    let firmware_version = card
+        .get_attribute_owned(Attribute::VendorIfdVersion)
+        .ok()
+        .filter(|bytes| bytes.len() >= 4)
+        .map(|bytes| {
+            let version = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            format!(
+                "{}.{}.{}",
+                version >> 24,
+                (version >> 16) & 0xFF,
+                version & 0xFFFF
+            )
+        });
+
+    if let Err((_, e)) = card.disconnect(Disposition::LeaveCard) {
+        log::debug!("Can't disconnect from reader {:?}: {}", reader_name, e);
+    }
+
+    (vendor_name, firmware_version)
+}
+
+/// Names of the readers with a card task. Their cards are not connected again for the
+/// attributes. None if the task pool is busy, then every reader is treated as used.
+fn readers_with_card_task() -> Option<Vec<String>> {
+    let pool = TASK_POOL.try_lock().ok()?;
+
+    Some(
+        pool.iter()
+            .filter(|card| card.managed_card.is_some())
+            .filter_map(|card| card.reader_name.clone())
+            .collect(),
+    )
+}
+
+/// Collects the information about all connected readers.
+pub fn get_readers_info() -> Result<Vec<ReaderInfo>, SmartCardError> {
+    let ctx = establish_context()?;
+
+    let mut readers_buf = [0; 2048];
+    let readers = ctx.list_readers(&mut readers_buf)?;
+    let busy_readers = readers_with_card_task();
+
+    Ok(readers
+        .map(|reader_name| {
+            let name = reader_name.to_string_lossy().into_owned();
+            let busy = match &busy_readers {
+                Some(busy_readers) => busy_readers.contains(&name),
+                None => true,
+            };
+
+            let (vendor_name, firmware_version) = if busy {
+                log::debug!("Reader {} has a card task, attributes are not read", name);
+                (None, None)
+            } else {
+                get_reader_attributes(&ctx, reader_name)
+            };
+
+            ReaderInfo {
+                name,
+                vendor_name,
+                firmware_version,
+                is_virtual: is_virtual_reader(reader_name),
+            }
+        })
+        .collect())
+}
+
+/// Lists the connected readers with their vendor and firmware information.
+#[tauri::command]
+pub fn list_readers() -> Result<Vec<ReaderInfo>, String> {
+    get_readers_info().map_err(|e| {
+        log::error!("Failed to list readers: {}", e);
+        format!("Failed to list readers: {}", e)
+    })
+}
+
+/// Collects the names of the real readers, without connecting to them.
+fn get_reader_names() -> Result<Vec<String>, SmartCardError> {
+    let ctx = establish_context()?;
+
+    let mut readers_buf = [0; 2048];
+    let readers = ctx.list_readers(&mut readers_buf)?;
+
+    Ok(readers
+        .filter(|reader_name| !is_virtual_reader(reader_name))
+        .map(|reader_name| reader_name.to_string_lossy().into_owned())
+        .collect())
+}
+
+/// Lists the names of the real readers, for the preferred reader dropdown of a card.
+/// Unlike `list_readers`, the readers are not connected, so no card is reset.
+#[tauri::command]
+pub fn list_readers_for_selection() -> Result<Vec<String>, String> {
+    get_reader_names().map_err(|e| {
+        log::error!("Failed to list readers: {}", e);
+        format!("Failed to list readers: {}", e)
+    })
+}
+
+//////////////////////////////////////////////////
+/// CARD WRAPER //////////////////////////////////
+/// //////////////////////////////////////////////
+#[derive(Clone)]
+pub struct ManagedCard {
+    inner: Option<Arc<Mutex<Card>>>, // None for a stub card without the physical card, see `stub`.
+    reader_name: Arc<CStr>,
+    protocol: Protocols,
+    iccid: Arc<StdMutex<Option<String>>>, // Shared between clones so a refresh is visible everywhere.
+    pub generation: OnceCell<CardGeneration>,
+    breaker: Arc<StdMutex<CircuitBreaker>>, // Failure counter, a reinserted card gets a new one.
+    exchange: Arc<Mutex<()>>, // Held for a whole logical exchange, see `lock_exchange`.
+}
+
+/// Circuit breaker of the card: after too many failed APDUs within the window,
+/// the card is not touched until the cooldown is over.
+#[derive(Debug, Default)]
+struct CircuitBreaker {
+    failures: u32,                 // Failures in a row within the window.
+    window_start: Option<Instant>, // Time of the first failure of the window.
+    open_until: Option<Instant>,   // Transmits are blocked until this moment.
+}
+
+impl CircuitBreaker {
+    /// Returns true during the cooldown. After the cooldown the breaker is closed again.
+    fn is_open(&mut self) -> bool {
+        match self.open_until {
+            Some(open_until) if Instant::now() < open_until => true,
+            Some(_) => {
+                *self = Self::default();
+                false
+            }
+            None => false,
+        }
+    }
+
+    fn record_success(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Counts the failure, returns true if the breaker has been tripped by it.
+    fn record_failure(&mut self, threshold: u32, window: Duration, cooldown: Duration) -> bool {
+        let now = Instant::now();
+
+        if self
+            .window_start
+            .map_or(true, |start| now.duration_since(start) > window)
+        {
+            self.window_start = Some(now);
+            self.failures = 0;
+        }
+
+        self.failures += 1;
+        if self.failures >= threshold {
+            self.open_until = Some(now + cooldown);
+            return true;
+        }
+
+        false
+    }
+}
+
+impl std::fmt::Debug for ManagedCard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ManagedCard")
+            .field("reader_name", &self.reader_name)
+            .field("protocol", &self.protocol)
+            .field("iccid", &self.iccid)
+            .finish()
+    }
+}
+
+/// Normalizes the APDU in hex as the servers send it: the whitespace and an optional "0x"
+/// prefix are removed and the digits are uppercased. The error names the position of the
+/// offending character in the original input.
+pub fn normalize_apdu_hex(apdu_hex: &str) -> Result<String, String> {
+    let start = apdu_hex.len() - apdu_hex.trim_start().len();
+    let digits_start = match apdu_hex[start..].get(..2) {
+        Some("0x") | Some("0X") => start + 2,
+        _ => start,
+    };
+
+    let mut normalized = String::with_capacity(apdu_hex.len());
+    for (position, c) in apdu_hex[digits_start..].char_indices() {
+        if c.is_whitespace() {
+            continue;
+        }
+        if !c.is_ascii_hexdigit() {
+            return Err(format!(
+                "Invalid hex character '{}' at position {}",
+                c,
+                digits_start + position
+            ));
+        }
+        normalized.push(c.to_ascii_uppercase());
+    }
+
+    if normalized.len() % 2 != 0 {
+        return Err(format!(
+            "Odd number of hex digits ({}), the last byte is incomplete",
+            normalized.len()
+        ));
+    }
+
+    Ok(normalized)
+}
+
+/// Waits for the card lock from a blocking thread, within `CARD_LOCK_TIMEOUT`.
+/// A panicked transmit or a reconnect that is mid-flight must not freeze the authentication
+/// of this card forever.
+fn lock_card_blocking(card: &Mutex<Card>) -> Result<MutexGuard<'_, Card>, SmartCardError> {
+    let started = Instant::now();
+    let mut warned = false;
+    loop {
+        match card.try_lock() {
+            Ok(guard) => {
+                debug!("Lock acquired in {:?}. Transmitting...", started.elapsed());
+                return Ok(guard);
+            }
+            Err(_) => {
+                let waited = started.elapsed();
+                if waited >= CARD_LOCK_TIMEOUT {
+                    error!(
+                        "Card lock was not acquired within {:?}. The card seems to be stuck.",
+                        CARD_LOCK_TIMEOUT
+                    );
+                    return Err(SmartCardError::Other(format!(
+                        "Lock timeout: card is busy for more than {:?}",
+                        CARD_LOCK_TIMEOUT
+                    )));
+                }
+                if !warned && waited >= CARD_LOCK_WARN_THRESHOLD {
+                    warn!("Waiting for the card lock for {:?} already...", waited);
+                    warned = true;
+                }
+                std::thread::sleep(CARD_LOCK_RETRY_INTERVAL);
             }
-            log::debug!("Available readers found");
         }
-        Err(e) => {
-            log::error!("Failed to list readers: {:?}", e);
-            return Ok(());
+    }
+}
+
+/// Emits the APDU to the protocol console of the UI, only called with the `apdu_events` option.
+/// The response carries the time since the command was `started`. When redacted, only the
+/// header of the command and the status word of the response are shown with the data length.
+fn report_apdu(
+    client_id: &str,
+    direction: &str,
+    apdu_hex: &str,
+    started: Option<Instant>,
+    redact: bool,
+) {
+    let is_response = started.is_some();
+    let split = if is_response {
+        apdu_hex.len().saturating_sub(4)
+    } else {
+        apdu_hex.len().min(8)
+    };
+    let (head, tail) = apdu_hex.split_at(split);
+
+    let hex = match (redact, is_response) {
+        (false, _) => apdu_hex.to_string(),
+        (true, false) if tail.is_empty() => head.to_string(),
+        (true, false) => format!("{}[{} bytes]", head, tail.len() / 2),
+        (true, true) if head.is_empty() => tail.to_string(),
+        (true, true) => format!("[{} bytes]{}", head.len() / 2, tail),
+    };
+
+    emit_apdu_event(
+        "global-apdu",
+        ApduPayload {
+            card_number: client_id.to_string(),
+            direction: direction.to_string(),
+            hex,
+            status_word: is_response.then(|| tail.to_uppercase()),
+            elapsed_ms: started.map(|started| started.elapsed().as_millis() as u64),
+        },
+    );
+}
+
+/// Builds the READ BINARY of `le` bytes at the offset in the current EF.
+/// Up to `MAX_SHORT_OFFSET` the offset is encoded in P1-P2 (b8 of P1 set would mean a short
+/// EF identifier). Beyond it the odd instruction B1 carries the offset in the data object 54,
+/// the answer is wrapped in the data object 53, see `unwrap_offset_data`.
+fn read_binary_apdu(offset: usize, le: usize) -> Vec<u8> {
+    let le = (le & 0xFF) as u8; // 256 is encoded as 00
+    if offset <= MAX_SHORT_OFFSET {
+        return vec![0x00, 0xB0, (offset >> 8) as u8, offset as u8, le];
+    }
+
+    let offset_bytes: Vec<u8> = (offset as u32)
+        .to_be_bytes()
+        .into_iter()
+        .skip_while(|byte| *byte == 0)
+        .collect();
+    let mut apdu = vec![0x00, 0xB1, 0x00, 0x00, (offset_bytes.len() + 2) as u8, 0x54];
+    apdu.push(offset_bytes.len() as u8);
+    apdu.extend(offset_bytes);
+    apdu.push(le);
+    apdu
+}
+
+/// Returns the content of the data object 53 answered by READ BINARY with the odd instruction.
+fn unwrap_offset_data(data: &[u8]) -> Result<&[u8], String> {
+    let (start, len) = match data {
+        [] => return Ok(data),
+        [0x53, 0x81, len, ..] => (3, *len as usize),
+        [0x53, 0x82, high, low, ..] => (4, u16::from_be_bytes([*high, *low]) as usize),
+        [0x53, len, ..] if *len < 0x80 => (2, *len as usize),
+        _ => {
+            return Err(format!(
+                "Unexpected READ BINARY data: {}",
+                hex::encode_upper(data)
+            ))
+        }
+    };
+    Ok(&data[start..(start + len).min(data.len())])
+}
+
+/// Returns the command with Le set to `le`: Le is replaced for a command that has it
+/// (case 2 and 4), or appended to a command without it (case 1 and 3). An extended length
+/// command (00 after the header, then two bytes of Lc or Le) gets a two byte Le.
+fn with_le(apdu: &[u8], le: u8) -> Vec<u8> {
+    let mut command = apdu.to_vec();
+
+    if let [_, _, _, _, 0x00, body @ ..] = apdu {
+        if body.len() >= 2 {
+            let le_len = match body.len() {
+                2 => 2, // Case 2E, only Le
+                len => {
+                    let lc = u16::from_be_bytes([body[0], body[1]]) as usize;
+                    (len - 2).saturating_sub(lc) // 0 for case 3E, 2 for case 4E
+                }
+            };
+            command.truncate(command.len() - le_len);
+            command.extend([0x00, le]);
+            return command;
         }
     }
 
-    let mut reader_states = vec![
-        // Listen for reader insertions/removals, if supported.
-        ReaderState::new(PNP_NOTIFICATION(), State::UNAWARE),
-    ];
+    let has_le = match apdu.len() {
+        0..=4 => false,
+        5 => true,
+        len => len > 5 + apdu[4] as usize,
+    };
+    if has_le {
+        command.pop();
+    }
+    command.push(le);
+    command
+}
 
-    // setup readers states. Getting changes and other inits
-    if let Err(e) = setup_reader_states(&ctx, &mut readers_buf, &mut reader_states) {
-        log::error!("Failed to setup reader states: {:?}", e);
+/// What is done after a response, by its status word, see `transmit_chained`.
+enum StatusAction {
+    Done,             // The status word is final.
+    ResendWithLe(u8), // "6Cxx": wrong Le, the same command is sent again with Le = xx.
+    GetResponse(u8),  // "61xx" with T=0: xx more bytes are fetched with GET RESPONSE.
+}
+
+impl StatusAction {
+    fn from_status(sw1: u8, sw2: u8, protocol: Protocols) -> Self {
+        match sw1 {
+            0x6C => StatusAction::ResendWithLe(sw2),
+            0x61 if protocol == Protocols::T0 => StatusAction::GetResponse(sw2),
+            _ => StatusAction::Done,
+        }
     }
-    // waiting for the status change
-    ctx.get_status_change(Some(Duration::from_secs(1)), &mut reader_states)
-        .expect("failed to get status change");
+}
 
-    process_reader_states(&mut reader_states)
-        .await
-        .map_err(|e| format!("Processing failed: {}", e))?;
+/// Transmits the APDU and returns the response in hex, after the post-processing of the
+/// status word:
+/// - "6Cxx" (wrong length) re-sends the same command with Le = xx once, so the data is
+///   returned instead of the error;
+/// - for T=0 cards "61xx" is followed by GET RESPONSE until the whole response is received,
+///   with T=1 the reader does the chaining itself.
+///
+/// The data is concatenated and ends with the final status word.
+fn transmit_chained(
+    card: &Card,
+    protocol: Protocols,
+    apdu: &[u8],
+) -> Result<String, SmartCardError> {
+    let mut rapdu_buf = [0u8; MAX_BUFFER_SIZE];
+    let response = chain_responses(protocol, apdu, |command| {
+        card.transmit(command, &mut rapdu_buf)
+            .map(<[u8]>::to_vec)
+            .map_err(|err| {
+                error!("APDU transmit failed: {}", err);
+                SmartCardError::from(err)
+            })
+    })?;
 
-    Ok(())
+    let encoded = hex::encode(response);
+    debug!("APDU transmit success. Encoded response: {}", encoded);
+    Ok(encoded)
 }
-//////////////////////////////////////////////////
-/// CARD WRAPER //////////////////////////////////
-/// //////////////////////////////////////////////
-#[derive(Clone)]
-pub struct ManagedCard {
-    inner: Arc<Mutex<Card>>,
-    reader_name: Arc<CStr>,
+
+/// Sends the APDU with `transmit` and does the status word post-processing of
+/// `transmit_chained`. Returns the concatenated data with the final status word.
+fn chain_responses<E>(
     protocol: Protocols,
-    pub iccid: OnceCell<String>,
+    apdu: &[u8],
+    mut transmit: impl FnMut(&[u8]) -> Result<Vec<u8>, E>,
+) -> Result<Vec<u8>, E> {
+    let mut response = Vec::new();
+    let mut command = apdu.to_vec();
+    let mut chained = 0;
+    let mut le_corrected = false; // Only once per command, a card repeating 6Cxx is answered as is
+
+    loop {
+        let received = transmit(&command)?;
+        response.extend_from_slice(&received);
+
+        let &[.., sw1, sw2] = received.as_slice() else {
+            break;
+        };
+        match StatusAction::from_status(sw1, sw2, protocol) {
+            StatusAction::ResendWithLe(le) if !le_corrected => {
+                debug!("Wrong length, the command is sent again with Le {:02X}", le);
+                response.truncate(response.len() - 2);
+                command = with_le(&command, le);
+                le_corrected = true;
+            }
+            StatusAction::GetResponse(available) => {
+                if chained == MAX_GET_RESPONSE_CHAIN {
+                    warn!(
+                        "GET RESPONSE chain exceeds {} commands. The response is cut.",
+                        MAX_GET_RESPONSE_CHAIN
+                    );
+                    break;
+                }
+
+                // GET RESPONSE with the number of available bytes from the status word
+                response.truncate(response.len() - 2);
+                command = vec![0x00, 0xC0, 0x00, 0x00, available];
+                le_corrected = false;
+                chained += 1;
+            }
+            _ => break,
+        }
+    }
+
+    if chained > 0 {
+        debug!("T=0 response is received with {} GET RESPONSE", chained);
+    }
+
+    Ok(response)
+}
+
+/// Decides the outcome of `ManagedCard::reconnect` from the result of the reconnect, the card
+/// is recreated only if the reconnect failed. Returns an error when both fail.
+async fn reconnect_or_recreate<E, F>(
+    reader_name: &str,
+    reconnected: Result<(), E>,
+    recreate: impl FnOnce() -> F,
+) -> Result<(), Box<dyn StdError + Send + Sync>>
+where
+    E: std::fmt::Debug + std::fmt::Display,
+    F: std::future::Future<Output = Result<(), Box<dyn StdError + Send + Sync>>>,
+{
+    let Err(reconnect_err) = reconnected else {
+        debug!("Card reconnected successfully for reader: {}", reader_name);
+        return Ok(());
+    };
+
+    warn!(
+        "Failed to reconnect card: {:?} for reader: {}. Will try to recreate.",
+        reconnect_err, reader_name
+    );
+
+    recreate().await.map_err(|e| {
+        error!(
+            "Failed to recreate card after reconnect failure for reader {}: {}",
+            reader_name, e
+        );
+        format!(
+            "reconnect failed ({}) and recreate failed ({})",
+            reconnect_err, e
+        )
+        .into()
+    })
 }
 
 impl ManagedCard {
-    pub fn new(
+    pub async fn new(
         reader_name: &CStr,
         protocol: Protocols,
     ) -> Result<Self, Box<dyn StdError + Send + Sync>> {
@@ -546,75 +2745,187 @@ impl ManagedCard {
             protocol
         );
 
-        let card = Self::create_card(reader_name, protocol)?;
+        let card = Self::create_card(reader_name, protocol).await?;
         debug!(
             "Card successfully created for reader: '{}'",
             reader_name.to_string_lossy()
         );
 
         Ok(Self {
-            inner: Arc::new(Mutex::new(card)),
+            inner: Some(Arc::new(Mutex::new(card))),
             reader_name: Arc::from(reader_name.to_owned()),
             protocol,
-            iccid: OnceCell::new(),
+            iccid: Arc::new(StdMutex::new(None)),
+            generation: OnceCell::new(),
+            breaker: Arc::new(StdMutex::new(CircuitBreaker::default())),
+            exchange: Arc::new(Mutex::new(())),
         })
     }
 
-    pub fn create_card(
+    /// Creates a card without the physical card. It has the cached ICCID and answers every
+    /// APDU with the dry-run response, used to test the broker side, see `connect_card`.
+    pub fn stub(reader_name: &CStr, protocol: Protocols, iccid: String) -> Self {
+        let generation = OnceCell::new();
+        let _ = generation.set(CardGeneration::Unknown);
+
+        Self {
+            inner: None,
+            reader_name: Arc::from(reader_name.to_owned()),
+            protocol,
+            iccid: Arc::new(StdMutex::new(Some(iccid))),
+            generation,
+            breaker: Arc::new(StdMutex::new(CircuitBreaker::default())),
+            exchange: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Disconnects the card without resetting it, unlike the drop. A card task connecting
+    /// to the same card at this time keeps the state of its card.
+    pub fn leave(self) {
+        let Some(inner) = self.inner else {
+            return;
+        };
+
+        match Arc::try_unwrap(inner) {
+            Ok(card) => {
+                if let Err((_, e)) = card.into_inner().disconnect(Disposition::LeaveCard) {
+                    warn!(
+                        "Can't disconnect from reader {}: {}",
+                        self.reader_name.to_string_lossy(),
+                        e
+                    );
+                }
+            }
+            Err(_) => debug!(
+                "Card in reader {} is still shared, it is not disconnected",
+                self.reader_name.to_string_lossy()
+            ),
+        }
+    }
+
+    /// Returns whether the card is a stub without the physical card.
+    pub fn is_stub(&self) -> bool {
+        self.inner.is_none()
+    }
+
+    /// Locks the card for a logical exchange, e.g. the whole authentication, so the APDU
+    /// sequences of the card task and of the commands don't interleave on the card.
+    ///
+    /// Locking order: the exchange lock first, then the I/O permit and the card connection
+    /// taken by every transmit. Never wait for the exchange lock while holding the card
+    /// connection or the task pool, and the holder must not lock it a second time.
+    pub async fn lock_exchange(&self) -> OwnedMutexGuard<()> {
+        Arc::clone(&self.exchange).lock_owned().await
+    }
+
+    /// Locks the card for a command, waiting a bounded time for the exchange in progress.
+    pub async fn wait_exchange(&self) -> Result<OwnedMutexGuard<()>, String> {
+        tokio::time::timeout(EXCHANGE_WAIT_TIMEOUT, self.lock_exchange())
+            .await
+            .map_err(|_| "The card is busy with the authentication, try again later".to_string())
+    }
+
+    /// Locks the card for a command only if no exchange is in progress.
+    pub fn try_lock_exchange(&self) -> Option<OwnedMutexGuard<()>> {
+        Arc::clone(&self.exchange).try_lock_owned().ok()
+    }
+
+    /// Returns the connection of the physical card, a stub card has none.
+    fn card(&self) -> Result<Arc<Mutex<Card>>, Box<dyn StdError + Send + Sync>> {
+        self.inner
+            .clone()
+            .ok_or_else(|| "Stub card has no card connection".into())
+    }
+
+    pub async fn create_card(
         reader_name: &CStr,
         protocol: Protocols,
     ) -> Result<Card, Box<dyn StdError + Send + Sync>> {
-        let ctx = Context::establish(Scope::User).map_err(|err| {
+        let ctx = establish_context().map_err(|err| {
             log::error!("Failed to establish context: {}", err);
             Box::<dyn StdError + Send + Sync>::from(err)
         })?;
 
-        let card = ctx
-            .connect(reader_name, ShareMode::Shared, protocol)
-            .map_err(|err| {
-                log::error!("Failed to connect to card: {}", err);
-                Box::<dyn StdError + Send + Sync>::from(err)
-            })?;
+        // A sharing violation is usually transient (another process or a handle being released),
+        // so the connect is retried a few times with a growing pause
+        let mut backoff = SHARING_VIOLATION_BACKOFF;
+        let mut retries = 0;
+        loop {
+            match ctx.connect(reader_name, ShareMode::Shared, protocol) {
+                Ok(card) => return Ok(card),
+                Err(pcsc::Error::SharingViolation) if retries < SHARING_VIOLATION_RETRIES => {
+                    retries += 1;
+                    warn!(
+                        "Card in reader {} is in use. Retry {}/{} in {:?}",
+                        reader_name.to_string_lossy(),
+                        retries,
+                        SHARING_VIOLATION_RETRIES,
+                        backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(pcsc::Error::SharingViolation) => {
+                    error!(
+                        "Card in reader {} is still in use after {} retries",
+                        reader_name.to_string_lossy(),
+                        SHARING_VIOLATION_RETRIES
+                    );
+
+                    let payload = NotificationPayload {
+                        notification_type: "card".to_string(),
+                        message: format!(
+                            "The card in reader {} is used by another application. Close it and sync the reader.",
+                            reader_name.to_string_lossy()
+                        ),
+                    };
+                    emit_notification_event("global-notification", payload);
 
-        Ok(card)
+                    return Err(Box::new(SmartCardError::SharingViolation));
+                }
+                Err(err) => {
+                    log::error!("Failed to connect to card: {}", err);
+                    return Err(Box::new(SmartCardError::from(err)));
+                }
+            }
+        }
     }
 
-    pub async fn reconnect(&self) {
+    /// Resets the card with a reconnect, the card object is recreated if the reconnect fails.
+    /// Returns an error when both fail: the card is not usable anymore and its task is dropped
+    /// by the caller instead of answering every APDU with an error.
+    pub async fn reconnect(&self) -> Result<(), Box<dyn StdError + Send + Sync>> {
         debug!(
             "Attempting to reconnect card for reader: {}",
             self.reader_name.to_string_lossy()
         );
 
-        let mut card = self.inner.lock().await;
+        let Some(inner) = &self.inner else {
+            debug!("Stub card has nothing to reconnect");
+            return Ok(());
+        };
+        // The lock is released before the recreate, which locks the card again
+        let reconnected =
+            inner
+                .lock()
+                .await
+                .reconnect(ShareMode::Shared, Protocols::ANY, Disposition::ResetCard);
 
-        match card.reconnect(ShareMode::Shared, Protocols::ANY, Disposition::ResetCard) {
-            Ok(_) => {
-                debug!(
-                    "Card reconnected successfully for reader: {}",
-                    self.reader_name.to_string_lossy()
-                );
-            }
-            Err(e) => {
-                warn!(
-                    "Failed to reconnect card: {:?} for reader: {}. Will try to recreate.",
-                    e,
-                    self.reader_name.to_string_lossy()
-                );
+        reconnect_or_recreate(&self.reader_name.to_string_lossy(), reconnected, || {
+            self.recreate()
+        })
+        .await
+    }
 
-                if let Err(e) = self.recreate().await {
-                    error!(
-                        "Failed to recreate card after reconnect failure for reader {}: {}",
-                        self.reader_name.to_string_lossy(),
-                        e
-                    );
-                }
-            }
-        }
+    /// Returns the protocol the card has been connected with.
+    pub fn protocol(&self) -> Protocols {
+        self.protocol
     }
 
     pub async fn recreate(&self) -> Result<(), Box<dyn StdError + Send + Sync>> {
-        let new_card = Self::create_card(&self.reader_name, self.protocol)?;
-        let mut lock = self.inner.lock().await;
+        let inner = self.card()?;
+        let new_card = Self::create_card(&self.reader_name, self.protocol).await?;
+        let mut lock = inner.lock().await;
         *lock = new_card;
 
         info!(
@@ -666,75 +2977,427 @@ impl ManagedCard {
     //     }
     // }
 
+    /// Sends the APDU to the card and returns the response in hex.
+    /// For T=0 cards a "61xx" status is followed by GET RESPONSE, see `transmit_chained`.
     pub async fn apdu_transmit(
         &self,
         apdu_hex: &str,
     ) -> Result<String, Box<dyn StdError + Send + Sync>> {
-        use crate::smart_card::MAX_BUFFER_SIZE;
-
         debug!(
             "apdu_transmit() called for reader: {} with APDU HEX: {}",
             self.reader_name.to_string_lossy(),
             apdu_hex
         );
 
-        let apdu = match hex::decode(apdu_hex) {
-            Ok(data) => {
-                debug!("APDU decoded successfully: {:?}", data);
-                data
+        let apdu = match normalize_apdu_hex(apdu_hex).map(hex::decode) {
+            Ok(Ok(data)) => {
+                debug!("APDU decoded successfully: {:?}", data);
+                data
+            }
+            Ok(Err(err)) => {
+                error!("Failed to decode APDU '{}': {}", apdu_hex, err);
+                return Err(format!("Decode error: {}", err).into());
+            }
+            Err(err) => {
+                error!("Invalid APDU '{}': {}", apdu_hex, err);
+                return Err(format!("Invalid APDU: {}", err).into());
+            }
+        };
+
+        // Wait for a free I/O slot, the permit is held until the transmission is complete
+        let _permit = CARD_IO_SEMAPHORE.acquire().await?;
+
+        let card = self.card()?;
+        let protocol = self.protocol;
+
+        debug!("Cloned card for blocking transmission. Sending to spawn_blocking...");
+
+        let response = tauri::async_runtime::spawn_blocking(move || {
+            debug!("Entered spawn_blocking thread. Locking card...");
+
+            let locked = lock_card_blocking(&card)?;
+            transmit_chained(&locked, protocol, &apdu)
+        })
+        .await??;
+
+        debug!(
+            "apdu_transmit() complete for reader: {}. Final response: {}",
+            self.reader_name.to_string_lossy(),
+            response
+        );
+
+        Ok(response)
+    }
+
+    /// Sends the APDUs to the card in a single PC/SC transaction and returns all responses.
+    /// The card lock is held for the whole sequence, so no reconnect or other APDU gets in
+    /// between related reads. The first failure aborts the sequence.
+    pub async fn apdu_transmit_batch(
+        &self,
+        apdus_hex: &[String],
+    ) -> Result<Vec<String>, Box<dyn StdError + Send + Sync>> {
+        debug!(
+            "apdu_transmit_batch() called for reader: {} with {} APDUs",
+            self.reader_name.to_string_lossy(),
+            apdus_hex.len()
+        );
+
+        let apdus = apdus_hex
+            .iter()
+            .map(|apdu_hex| {
+                normalize_apdu_hex(apdu_hex)
+                    .and_then(|apdu| hex::decode(apdu).map_err(|err| err.to_string()))
+                    .map_err(|err| format!("Invalid APDU '{}': {}", apdu_hex, err))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Wait for a free I/O slot, the permit is held until the transmission is complete
+        let _permit = CARD_IO_SEMAPHORE.acquire().await?;
+
+        let card = self.card()?;
+        let protocol = self.protocol;
+
+        let responses = tauri::async_runtime::spawn_blocking(move || {
+            // The guard and the transaction are released when they go out of scope, on error too
+            let mut locked = lock_card_blocking(&card)?;
+            let transaction = locked.transaction().map_err(|err| {
+                error!("Failed to begin the card transaction: {}", err);
+                SmartCardError::from(err)
+            })?;
+
+            let responses = apdus
+                .iter()
+                .map(|apdu| transmit_chained(&transaction, protocol, apdu))
+                .collect::<Result<Vec<_>, _>>();
+            responses
+        })
+        .await??;
+
+        debug!(
+            "apdu_transmit_batch() complete for reader: {}. Responses: {:?}",
+            self.reader_name.to_string_lossy(),
+            responses
+        );
+
+        Ok(responses)
+    }
+
+    /// Reads `length` bytes from the start of the EF, in the DF with the given name (AID) or
+    /// in the current DF. The DF and the EF are selected and the EF is read in chunks at
+    /// increasing offsets, all in one transaction.
+    /// Less than `length` is returned when the card signals the end of the file first.
+    pub async fn read_binary(
+        &self,
+        df_name: Option<&[u8]>,
+        ef_id: u16,
+        length: usize,
+    ) -> Result<Vec<u8>, Box<dyn StdError + Send + Sync>> {
+        debug!(
+            "read_binary() called for reader: {} with DF {:?}, EF {:04X}, {} bytes",
+            self.reader_name.to_string_lossy(),
+            df_name.map(hex::encode_upper),
+            ef_id,
+            length
+        );
+
+        // Wait for a free I/O slot, the permit is held until the whole file is read
+        let _permit = CARD_IO_SEMAPHORE.acquire().await?;
+
+        let card = self.card()?;
+        let protocol = self.protocol;
+        let df_name = df_name.map(<[u8]>::to_vec);
+
+        let data = tauri::async_runtime::spawn_blocking(
+            move || -> Result<Vec<u8>, Box<dyn StdError + Send + Sync>> {
+                // The guard and the transaction are released when they go out of scope, on error too
+                let mut locked = lock_card_blocking(&card)?;
+                let transaction = locked.transaction().map_err(SmartCardError::from)?;
+
+                if let Some(df_name) = df_name {
+                    let mut select_df_apdu = vec![0x00, 0xA4, 0x04, 0x0C, df_name.len() as u8];
+                    select_df_apdu.extend(&df_name);
+                    let select_df = transmit_chained(&transaction, protocol, &select_df_apdu)?;
+                    if !select_df.ends_with("9000") {
+                        return Err(format!(
+                            "Failed to select DF {}: {}",
+                            hex::encode_upper(&df_name),
+                            select_df
+                        )
+                        .into());
+                    }
+                }
+
+                let [id_high, id_low] = ef_id.to_be_bytes();
+                let select_apdu = [0x00, 0xA4, 0x02, 0x0C, 0x02, id_high, id_low];
+                let select = transmit_chained(&transaction, protocol, &select_apdu)?;
+                if !select.ends_with("9000") {
+                    return Err(format!("Failed to select EF {:04X}: {}", ef_id, select).into());
+                }
+
+                let mut data = Vec::with_capacity(length);
+                while data.len() < length {
+                    let offset = data.len();
+                    // The data object header of the odd instruction takes up to 4 bytes
+                    let chunk_len = if offset <= MAX_SHORT_OFFSET {
+                        (length - offset).min(READ_BINARY_CHUNK)
+                    } else {
+                        (length - offset).min(READ_BINARY_CHUNK - 4)
+                    };
+                    let le = if offset <= MAX_SHORT_OFFSET {
+                        chunk_len
+                    } else {
+                        chunk_len + 4
+                    };
+
+                    let response =
+                        transmit_chained(&transaction, protocol, &read_binary_apdu(offset, le))?;
+                    let (body, status) = response.split_at(response.len().saturating_sub(4));
+                    let body = hex::decode(body)
+                        .map_err(|e| format!("Failed to decode READ BINARY response: {}", e))?;
+                    let mut chunk = if offset <= MAX_SHORT_OFFSET {
+                        body
+                    } else {
+                        unwrap_offset_data(&body)?.to_vec()
+                    };
+                    chunk.truncate(chunk_len);
+
+                    match status {
+                        "9000" => {
+                            let end_of_file = chunk.len() < chunk_len;
+                            data.extend(chunk);
+                            if end_of_file {
+                                break;
+                            }
+                        }
+                        // End of file reached before the requested bytes
+                        "6282" => {
+                            data.extend(chunk);
+                            break;
+                        }
+                        // The offset is past the end of the file
+                        "6b00" if offset > 0 => break,
+                        _ => {
+                            return Err(format!(
+                                "READ BINARY of EF {:04X} at offset {} returned {}",
+                                ef_id, offset, status
+                            )
+                            .into())
+                        }
+                    }
+                }
+
+                Ok(data)
+            },
+        )
+        .await??;
+
+        debug!(
+            "read_binary() complete for reader: {}. {} bytes of EF {:04X} are read",
+            self.reader_name.to_string_lossy(),
+            data.len(),
+            ef_id
+        );
+
+        Ok(data)
+    }
+
+    /// Sends the APDU from the server and returns the response in hex. A failure is answered
+    /// with "6F00", a card removed or reset during the exchange with `CardInterrupted`.
+    pub async fn send_apdu(
+        &self,
+        apdu_hex: &str,
+        client_id: &str,
+    ) -> Result<String, CardInterrupted> {
+        // Normalized once, so the trace and the transmit see the same APDU
+        let apdu_hex = match normalize_apdu_hex(apdu_hex) {
+            Ok(apdu_hex) => apdu_hex,
+            Err(e) => {
+                error!("{} Invalid APDU '{}': {}", client_id, apdu_hex, e);
+                return Ok("6F00".to_string());
+            }
+        };
+        let apdu_hex = apdu_hex.as_str();
+
+        // The flags are checked once, a disabled trace adds nothing to the transmit path
+        let smart_card_config = get_smart_card_config();
+        let apdu_trace = smart_card_config.apdu_trace;
+        let redact = smart_card_config.redact_sensitive;
+        let events_started = smart_card_config.apdu_events.then(Instant::now);
+        if apdu_trace {
+            trace_apdu(client_id, "C-APDU", apdu_hex);
+        }
+        if events_started.is_some() {
+            report_apdu(client_id, "C-APDU", apdu_hex, None, redact);
+        }
+
+        let response = self.process_apdu(apdu_hex, client_id).await?;
+
+        if apdu_trace {
+            trace_apdu(client_id, "R-APDU", &response);
+        }
+        if let Some(started) = events_started {
+            report_apdu(client_id, "R-APDU", &response, Some(started), redact);
+        }
+
+        Ok(response)
+    }
+
+    /// Sends a sequence of APDUs from the server atomically, see `apdu_transmit_batch`.
+    /// The maintenance, dry-run and circuit breaker rules of `send_apdu` apply to the whole
+    /// sequence. A failed sequence is not retried and is answered with a single "6F00".
+    pub async fn send_apdu_batch(
+        &self,
+        apdus_hex: &[String],
+        client_id: &str,
+    ) -> Result<Vec<String>, CardInterrupted> {
+        let smart_card_config = get_smart_card_config();
+        let redact = smart_card_config.redact_sensitive;
+        let events_started = smart_card_config.apdu_events.then(Instant::now);
+        if smart_card_config.apdu_trace {
+            for apdu_hex in apdus_hex {
+                trace_apdu(client_id, "C-APDU", apdu_hex);
+            }
+        }
+        if events_started.is_some() {
+            for apdu_hex in apdus_hex {
+                report_apdu(client_id, "C-APDU", apdu_hex, None, redact);
+            }
+        }
+
+        let responses = if smart_card_config.maintenance {
+            warn!(
+                "{} Maintenance mode: {} APDUs are not transmitted",
+                client_id,
+                apdus_hex.len()
+            );
+            vec![MAINTENANCE_RESPONSE.to_string(); apdus_hex.len()]
+        } else if smart_card_config.dry_run || self.is_stub() {
+            info!(
+                "{} DRY RUN: APDUs {:?} are not transmitted",
+                client_id, apdus_hex
+            );
+            vec![smart_card_config.dry_run_response(); apdus_hex.len()]
+        } else if self.breaker.lock().unwrap().is_open() {
+            debug!(
+                "{} Circuit breaker is open: {} APDUs are not transmitted",
+                client_id,
+                apdus_hex.len()
+            );
+            vec!["6F00".to_string()]
+        } else {
+            match self.apdu_transmit_batch(apdus_hex).await {
+                Ok(responses) => {
+                    self.breaker.lock().unwrap().record_success();
+                    clear_card_error(client_id);
+                    responses
+                }
+                Err(e) => match TransmitFailure::from_error(e.as_ref()) {
+                    TransmitFailure::Interrupted { reset } => {
+                        let interrupted = CardInterrupted { reset };
+                        self.report_interrupted(client_id, interrupted).await;
+                        return Err(interrupted);
+                    }
+                    TransmitFailure::Failed(reason) => {
+                        error!("{} Failed to send the APDU sequence: {}", client_id, e);
+                        self.record_apdu_failure(client_id, &reason);
+                        vec!["6F00".to_string()]
+                    }
+                },
+            }
+        };
+
+        if smart_card_config.apdu_trace {
+            for response in &responses {
+                trace_apdu(client_id, "R-APDU", response);
             }
-            Err(err) => {
-                error!("Failed to decode APDU '{}': {}", apdu_hex, err);
-                return Err(format!("Decode error: {}", err).into());
+        }
+        if let Some(started) = events_started {
+            for response in &responses {
+                report_apdu(client_id, "R-APDU", response, Some(started), redact);
             }
-        };
+        }
 
-        let card = Arc::clone(&self.inner);
-        let apdu_cloned = apdu.clone();
+        Ok(responses)
+    }
 
-        debug!("Cloned card for blocking transmission. Sending to spawn_blocking...");
+    /// Sends the APDU to the card, the card is recreated and the APDU is retried once on failure.
+    async fn process_apdu(
+        &self,
+        apdu_hex: &str,
+        client_id: &str,
+    ) -> Result<String, CardInterrupted> {
+        debug!("{} Sending APDU command: {}", client_id, apdu_hex);
 
-        let response = tauri::async_runtime::spawn_blocking(move || {
-            debug!("Entered spawn_blocking thread. Preparing buffer and locking card...");
+        let smart_card_config = get_smart_card_config();
+        if smart_card_config.maintenance {
+            warn!(
+                "{} Maintenance mode: APDU {} is not transmitted",
+                client_id, apdu_hex
+            );
+            return Ok(MAINTENANCE_RESPONSE.to_string());
+        }
 
-            let mut rapdu_buf = [0u8; MAX_BUFFER_SIZE];
+        // Dry-run mode: the card is never touched, so its try-counter is safe.
+        // A stub card has no card to touch, it always answers like in the dry-run mode.
+        if smart_card_config.dry_run || self.is_stub() {
+            let response = smart_card_config.dry_run_response();
+            info!(
+                "{} DRY RUN: APDU {} is not transmitted, stub response: {}",
+                client_id, apdu_hex, response
+            );
+            return Ok(response);
+        }
 
-            let locked = card.blocking_lock();
-            debug!("Lock acquired. Transmitting...");
+        // Circuit breaker: a card failing over and over is not touched during the cooldown
+        if self.breaker.lock().unwrap().is_open() {
+            debug!(
+                "{} Circuit breaker is open: APDU {} is not transmitted",
+                client_id, apdu_hex
+            );
+            return Ok("6F00".to_string());
+        }
 
-            match locked.transmit(&apdu_cloned, &mut rapdu_buf) {
-                Ok(response) => {
-                    let encoded = hex::encode(response);
-                    debug!("APDU transmit success. Encoded response: {}", encoded);
-                    Ok(encoded)
-                }
-                Err(err) => {
-                    error!("APDU transmit failed: {}", err);
-                    Err(format!("Transmit error: {}", err))
-                }
+        match self.transmit_with_recreate(apdu_hex, client_id).await {
+            Ok(response) => {
+                self.breaker.lock().unwrap().record_success();
+                clear_card_error(client_id);
+                Ok(response)
             }
-        })
-        .await??;
-
-        debug!(
-            "apdu_transmit() complete for reader: {}. Final response: {}",
-            self.reader_name.to_string_lossy(),
-            response
-        );
-
-        Ok(response)
+            Err(TransmitFailure::Interrupted { reset }) => {
+                let interrupted = CardInterrupted { reset };
+                self.report_interrupted(client_id, interrupted).await;
+                Err(interrupted)
+            }
+            Err(TransmitFailure::Failed(reason)) => {
+                self.record_apdu_failure(client_id, &reason);
+                Ok("6F00".to_string())
+            }
+        }
     }
 
-    pub async fn send_apdu(&self, apdu_hex: &str, client_id: &str) -> String {
-        debug!("{} Sending APDU command: {}", client_id, apdu_hex);
-
+    /// Sends the APDU to the card, the card is recreated and the APDU is retried once on failure.
+    /// A removed or reset card is reported as interrupted right away, the retry can't help.
+    async fn transmit_with_recreate(
+        &self,
+        apdu_hex: &str,
+        client_id: &str,
+    ) -> Result<String, TransmitFailure> {
         // First attempt
         match self.apdu_transmit(apdu_hex).await {
             Ok(response) => {
                 debug!("{} APDU response: {:?}", client_id, response);
-                return response;
+                return Ok(response);
             }
             Err(err) => {
+                let failure = TransmitFailure::from_error(err.as_ref());
+                if let TransmitFailure::Interrupted { .. } = failure {
+                    error!(
+                        "{} Failed to send APDU: {}. The card is removed or reset.",
+                        client_id, err
+                    );
+                    return Err(failure);
+                }
+
                 error!(
                     "{} Failed to send APDU: {}. Attempting to recreate card...",
                     client_id, err
@@ -748,7 +3411,7 @@ impl ManagedCard {
                 "{} Failed to recreate card after APDU failure: {}",
                 client_id, e
             );
-            return "6F00".to_string();
+            return Err(TransmitFailure::from_error(e.as_ref()));
         }
 
         // Seccond attempt
@@ -758,28 +3421,120 @@ impl ManagedCard {
                     "{} APDU response (after recreate): {:?}",
                     client_id, response
                 );
-                response
+                Ok(response)
             }
             Err(retry_err) => {
                 error!(
                     "{} Retry failed: could not send APDU after recreate: {}",
                     client_id, retry_err
                 );
-                "6F00".to_string()
+                Err(TransmitFailure::from_error(retry_err.as_ref()))
+            }
+        }
+    }
+
+    /// Reports the card removed or reset in the middle of the exchange. A reset card is
+    /// reconnected, so the next authentication starts on a fresh card session. Only a
+    /// removed card is reported as REMOVED, the reset card is still in the reader.
+    async fn report_interrupted(&self, client_id: &str, interrupted: CardInterrupted) {
+        warn!(
+            "{} Card is {} during the exchange. The server abandons the authentication",
+            client_id,
+            interrupted.as_str()
+        );
+
+        if interrupted.reset {
+            if let Err(e) = self.reconnect().await {
+                error!("{} Card is not usable after the reset: {}", client_id, e);
             }
+            return;
+        }
+
+        emit_event(
+            "global-cards-sync",
+            TachoState {
+                iccid: self.iccid.lock().unwrap().clone().unwrap_or_default(),
+                reader_name: self.reader_name.to_string_lossy().into_owned(),
+                card_state: "State(REMOVED)".into(), // Parsed by the frontend like the PC/SC state.
+                card_number: client_id.to_string(),
+                online: None,
+                authentication: Some(false),
+                generation: self.generation.get().copied(),
+                atr: None,
+                protocol: None,
+                errored: None,
+            },
+        );
+    }
+
+    /// Counts the failed APDU and trips the circuit breaker after too many failures in a row.
+    fn record_apdu_failure(&self, client_id: &str, reason: &str) {
+        record_card_error(client_id, format!("APDU failed: {}", reason));
+
+        let smart_card_config = get_smart_card_config();
+        let threshold = smart_card_config.breaker_failures();
+        if threshold == 0 {
+            return;
+        }
+
+        let cooldown = smart_card_config.breaker_cooldown();
+        let tripped = self.breaker.lock().unwrap().record_failure(
+            threshold,
+            smart_card_config.breaker_window(),
+            cooldown,
+        );
+
+        if tripped {
+            error!(
+                "{} {} APDU failures in a row. Transmits are paused for {:?}",
+                client_id, threshold, cooldown
+            );
+
+            let payload = NotificationPayload {
+                notification_type: "card".to_string(),
+                message: format!(
+                    "Card {} failed {} times in a row. It is not used for {} s.",
+                    client_id,
+                    threshold,
+                    cooldown.as_secs()
+                ),
+            };
+            emit_notification_event("global-notification", payload);
+
+            // The card stays in the reader, only its status shows the open breaker
+            emit_event(
+                "global-cards-sync",
+                TachoState {
+                    iccid: self.iccid.lock().unwrap().clone().unwrap_or_default(),
+                    reader_name: self.reader_name.to_string_lossy().into_owned(),
+                    card_state: "PRESENT".into(),
+                    card_number: client_id.to_string(),
+                    online: None,
+                    authentication: None,
+                    generation: self.generation.get().copied(),
+                    atr: None,
+                    protocol: Some(protocol_name(self.protocol)),
+                    errored: Some(true),
+                },
+            );
         }
     }
 
+    /// Returns true while the circuit breaker of the card is open.
+    pub fn is_errored(&self) -> bool {
+        self.breaker.lock().unwrap().is_open()
+    }
+
     /// Returns the card ICCID using lazy caching.
     /// On first call, reads it from the card; subsequent calls return the cached value.
     pub async fn get_iccid(&self) -> Result<String, Box<dyn StdError + Send + Sync>> {
-        if let Some(cached) = self.iccid.get() {
+        if let Some(cached) = self.iccid.lock().unwrap().clone() {
             log::debug!(
                 "Returning cached ICCID for reader {}: {}",
                 self.reader_name.to_string_lossy(),
                 cached
             );
-            return Ok(cached.clone());
+            return Ok(cached);
         }
 
         log::debug!(
@@ -787,18 +3542,69 @@ impl ManagedCard {
             self.reader_name.to_string_lossy()
         );
 
-        // SELECT EF ICC (2FE2)
-        let select_result = self.apdu_transmit("00A4020C020002").await?;
+        let iccid = self.read_iccid().await?;
+
+        // Save ICCID, not got earlier
+        *self.iccid.lock().unwrap() = Some(iccid.clone());
+
+        Ok(iccid)
+    }
+
+    /// Re-reads the ICCID from the card bypassing the cache and replaces the cached value.
+    /// Useful when a reader glitch produced a wrong ICCID.
+    pub async fn refresh_iccid(&self) -> Result<String, Box<dyn StdError + Send + Sync>> {
+        log::debug!(
+            "refresh_iccid() started for reader: {}",
+            self.reader_name.to_string_lossy()
+        );
+
+        let iccid = self.read_iccid().await?;
 
-        if !select_result.ends_with("9000") {
+        let previous = self.iccid.lock().unwrap().replace(iccid.clone());
+        if previous.as_deref() != Some(iccid.as_str()) {
             log::warn!(
-                "SELECT EF ICC returned unexpected status: {}",
-                select_result
+                "ICCID for reader {} is changed: {:?} -> {}",
+                self.reader_name.to_string_lossy(),
+                previous,
+                iccid
             );
         }
 
-        // READ BINARY (10 байт)
-        let read_response = self.apdu_transmit("00B0000108").await?;
+        Ok(iccid)
+    }
+
+    /// Reads the ICCID from EF ICC of the card.
+    async fn read_iccid(&self) -> Result<String, Box<dyn StdError + Send + Sync>> {
+        // The APDU sequence can be overridden in the config for non-standard cards
+        let apdus = match get_smart_card_config().iccid_apdus() {
+            Some(apdus) => apdus,
+            None => {
+                // EF ICC (0002): clockStop, then the 8 bytes of cardExtendedSerialNumber
+                let data = self.read_binary(None, 0x0002, 9).await?;
+                if data.len() < 9 {
+                    return Err("EF ICC is shorter than expected".into());
+                }
+
+                let iccid = hex::encode_upper(&data[1..9]);
+                log::debug!("Final ICCID: {}", iccid);
+                return Ok(iccid);
+            }
+        };
+        let (read_apdu, select_apdus) = apdus.split_last().ok_or("ICCID APDU sequence is empty")?;
+
+        for select_apdu in select_apdus {
+            let select_result = self.apdu_transmit(select_apdu).await?;
+
+            if !select_result.ends_with("9000") {
+                log::warn!(
+                    "ICCID selection {} returned unexpected status: {}",
+                    select_apdu,
+                    select_result
+                );
+            }
+        }
+
+        let read_response = self.apdu_transmit(read_apdu).await?;
 
         let hex_data = read_response.strip_suffix("9000").unwrap_or(&read_response);
 
@@ -812,9 +3618,572 @@ impl ManagedCard {
 
         log::debug!("Final ICCID: {}", iccid);
 
-        // Save ICCID, not got earlier
-        let _ = self.iccid.set(iccid.clone());
-
         Ok(iccid)
     }
+
+    /// Reads the card number from EF Identification of the tachograph application.
+    /// Works for the driver and the company cards, both store it at the same offset.
+    pub async fn read_card_number(&self) -> Result<String, Box<dyn StdError + Send + Sync>> {
+        // SELECT DF Tachograph by AID (FF 54 41 43 48 4F), present on every generation
+        let select_df = self.apdu_transmit("00A4040C06FF544143484F").await?;
+        if !select_df.ends_with("9000") {
+            return Err(format!("Failed to select DF Tachograph: {}", select_df).into());
+        }
+
+        // SELECT EF Identification (0520)
+        let select_ef = self.apdu_transmit("00A4020C020520").await?;
+        if !select_ef.ends_with("9000") {
+            return Err(format!("Failed to select EF Identification: {}", select_ef).into());
+        }
+
+        // READ BINARY 16 bytes of cardNumber at offset 1, after cardIssuingMemberState
+        let read_response = self.apdu_transmit("00B0000110").await?;
+        let hex_data = read_response
+            .strip_suffix("9000")
+            .ok_or_else(|| format!("Failed to read EF Identification: {}", read_response))?;
+
+        let bytes = hex::decode(hex_data)
+            .map_err(|e| format!("Failed to decode card number hex: {}", e))?;
+
+        let card_number = String::from_utf8_lossy(&bytes)
+            .trim_matches(|c: char| c.is_whitespace() || c == '\0')
+            .to_uppercase();
+        if card_number.is_empty() {
+            return Err("Card number on the card is empty".into());
+        }
+
+        log::debug!("Card number read from the card: {}", card_number);
+
+        Ok(card_number)
+    }
+
+    /// Reads the card type from `EF Application_Identification` of the `Tachograph` DF.
+    pub async fn read_card_type(
+        &self,
+    ) -> Result<TachographCardType, Box<dyn StdError + Send + Sync>> {
+        let responses = self
+            .apdu_transmit_batch(&[
+                "00A4040C06FF544143484F".to_string(), // SELECT DF Tachograph
+                "00A4020C020501".to_string(),         // SELECT EF Application_Identification
+                "00B0000001".to_string(),             // READ BINARY typeOfTachographCardId
+            ])
+            .await?;
+        let type_id = response_data(&responses)?[2]
+            .first()
+            .copied()
+            .ok_or("EF Application_Identification is empty")?;
+
+        match type_id {
+            1 => Ok(TachographCardType::Driver),
+            2 => Ok(TachographCardType::Workshop),
+            3 => Ok(TachographCardType::Control),
+            4 => Ok(TachographCardType::Company),
+            other => Err(format!("Unknown tachograph card type: {}", other).into()),
+        }
+    }
+
+    /// Reads the holder from `EF Identification` of the `Tachograph` DF (Annex 1C, Appendix 2).
+    /// The record after `CardIdentification` depends on the card type, which is read from
+    /// `EF Application_Identification` first. The callers hold the exchange, see `lock_exchange`,
+    /// so nothing else selects another file in between.
+    pub async fn read_identity(&self) -> Result<CardIdentity, Box<dyn StdError + Send + Sync>> {
+        let card_type = self.read_card_type().await?;
+
+        // Length of the holder record: CardHolderIdentification of the card type
+        let holder_len = match card_type {
+            TachographCardType::Driver => 78,
+            TachographCardType::Workshop | TachographCardType::Control => 146,
+            TachographCardType::Company => 38,
+        };
+
+        // DF Tachograph, then CardIdentification (65 bytes) and the holder record,
+        // the DF is selected in the same transaction
+        let data = self
+            .read_binary(Some(DF_TACHOGRAPH), 0x0520, 65 + holder_len)
+            .await?;
+        if data.len() < 65 + holder_len {
+            return Err("EF Identification is shorter than expected".into());
+        }
+        let (card_id, holder) = data.split_at(65);
+
+        let full_name = |surname: &[u8], first_names: &[u8]| {
+            let name = format!("{} {}", decode_name(first_names), decode_name(surname));
+            name.trim().to_string()
+        };
+        let (holder_name, organisation_name) = match card_type {
+            TachographCardType::Driver => (Some(full_name(&holder[0..36], &holder[36..72])), None),
+            TachographCardType::Company => (None, Some(decode_name(&holder[0..36]))),
+            TachographCardType::Workshop | TachographCardType::Control => (
+                Some(full_name(&holder[72..108], &holder[108..144])),
+                Some(decode_name(&holder[0..36])),
+            ),
+        };
+
+        // cardExpiryDate is TimeReal, seconds since 1970-01-01 00:00 UTC
+        let expiry_secs = u32::from_be_bytes([card_id[61], card_id[62], card_id[63], card_id[64]]);
+        let expiry_date = chrono::DateTime::from_timestamp(expiry_secs as i64, 0)
+            .filter(|_| expiry_secs != 0)
+            .map(|time| time.format("%Y-%m-%d").to_string());
+
+        Ok(CardIdentity {
+            card_type,
+            card_number: String::from_utf8_lossy(&card_id[1..17])
+                .trim_matches(|c: char| c.is_whitespace() || c == '\0')
+                .to_string(),
+            issuing_authority: decode_name(&card_id[17..53]),
+            holder_name,
+            organisation_name,
+            expiry_date,
+        })
+    }
+
+    /// Reads the remaining PIN tries with VERIFY without the PIN, which doesn't use a try.
+    /// Only the workshop cards have a PIN, the other cards are reported as unknown.
+    pub async fn read_pin_tries(&self) -> Result<PinTries, Box<dyn StdError + Send + Sync>> {
+        let responses = self
+            .apdu_transmit_batch(&[
+                "00A4040C06FF544143484F".to_string(), // SELECT DF Tachograph
+                "00200000".to_string(),               // VERIFY without the PIN
+            ])
+            .await?;
+        if !responses[0].ends_with("9000") {
+            return Ok(PinTries::Unknown);
+        }
+
+        let verify = &responses[1];
+        Ok(PinTries::from_status(
+            &verify[verify.len().saturating_sub(4)..],
+        ))
+    }
+
+    /// Returns the card generation using lazy caching.
+    /// Degrades to `CardGeneration::Unknown` when the applications can't be selected.
+    /// The detection selects the applications, so the card is reset afterwards and the
+    /// authentication of the server starts on a fresh card session. It runs on the card
+    /// insertion, before the card task exists.
+    pub async fn get_generation(&self) -> CardGeneration {
+        if let Some(cached) = self.generation.get() {
+            return *cached;
+        }
+
+        let generation = match self.detect_generation().await {
+            Ok(generation) => generation,
+            Err(e) => {
+                log::warn!(
+                    "Failed to detect card generation for reader {}: {}",
+                    self.reader_name.to_string_lossy(),
+                    e
+                );
+                CardGeneration::Unknown
+            }
+        };
+
+        if let Err(e) = self.reconnect().await {
+            log::warn!(
+                "Failed to reset the card in reader {} after the generation detection: {}",
+                self.reader_name.to_string_lossy(),
+                e
+            );
+        }
+
+        log::info!(
+            "Card generation for reader {}: {:?}",
+            self.reader_name.to_string_lossy(),
+            generation
+        );
+
+        let _ = self.generation.set(generation);
+
+        generation
+    }
+
+    async fn detect_generation(&self) -> Result<CardGeneration, Box<dyn StdError + Send + Sync>> {
+        // SELECT DF Tachograph_G2 by AID (FF 53 4D 52 44 54)
+        let select_g2 = self.apdu_transmit("00A4040C06FF534D524454").await?;
+
+        if select_g2.ends_with("9000") {
+            // SELECT EF Application_Identification_V2 (0525), present on gen2v2 cards only
+            let select_v2 = self.apdu_transmit("00A4020C020525").await?;
+
+            return Ok(if select_v2.ends_with("9000") {
+                CardGeneration::Gen2v2
+            } else {
+                CardGeneration::Gen2v1
+            });
+        }
+
+        // SELECT DF Tachograph by AID (FF 54 41 43 48 4F)
+        let select_g1 = self.apdu_transmit("00A4040C06FF544143484F").await?;
+
+        Ok(if select_g1.ends_with("9000") {
+            CardGeneration::Gen1
+        } else {
+            CardGeneration::Unknown
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    type BoxError = Box<dyn StdError + Send + Sync>;
+
+    /// Runs `chain_responses` against a card answering with the given responses in order.
+    /// Returns the final response and the commands the card received.
+    fn chain_with_fake_card(
+        protocol: Protocols,
+        apdu: &[u8],
+        responses: &[&[u8]],
+    ) -> (Vec<u8>, Vec<Vec<u8>>) {
+        let mut responses: VecDeque<Vec<u8>> = responses.iter().map(|r| r.to_vec()).collect();
+        let mut commands = Vec::new();
+
+        let response = chain_responses(protocol, apdu, |command| {
+            commands.push(command.to_vec());
+            responses
+                .pop_front()
+                .ok_or_else(|| "Unexpected command".to_string())
+        })
+        .unwrap();
+
+        (response, commands)
+    }
+
+    #[test]
+    fn t0_response_is_chained_with_get_response() {
+        let (response, commands) = chain_with_fake_card(
+            Protocols::T0,
+            &[0x00, 0xB0, 0x00, 0x00, 0x00],
+            &[
+                &[0xAA, 0x61, 0x02],
+                &[0xBB, 0xCC, 0x61, 0x01],
+                &[0xDD, 0x90, 0x00],
+            ],
+        );
+
+        assert_eq!(response, [0xAA, 0xBB, 0xCC, 0xDD, 0x90, 0x00]);
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[1], [0x00, 0xC0, 0x00, 0x00, 0x02]);
+        assert_eq!(commands[2], [0x00, 0xC0, 0x00, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn t1_response_is_not_chained() {
+        let (response, commands) = chain_with_fake_card(
+            Protocols::T1,
+            &[0x00, 0xB0, 0x00, 0x00, 0x00],
+            &[&[0xAA, 0x61, 0x02]],
+        );
+
+        assert_eq!(response, [0xAA, 0x61, 0x02]);
+        assert_eq!(commands.len(), 1);
+    }
+
+    #[test]
+    fn t0_chain_is_cut_after_the_limit() {
+        let endless: Vec<&[u8]> = vec![&[0x61, 0x01]; MAX_GET_RESPONSE_CHAIN + 1];
+        let (response, commands) =
+            chain_with_fake_card(Protocols::T0, &[0x00, 0xB0, 0x00, 0x00, 0x00], &endless);
+
+        assert_eq!(response, [0x61, 0x01]);
+        assert_eq!(commands.len(), MAX_GET_RESPONSE_CHAIN + 1);
+    }
+
+    #[test]
+    fn wrong_length_is_sent_again_with_the_le() {
+        let (response, commands) = chain_with_fake_card(
+            Protocols::T1,
+            &[0x00, 0xB0, 0x00, 0x00, 0x00],
+            &[&[0x6C, 0x02], &[0xAA, 0xBB, 0x90, 0x00]],
+        );
+
+        assert_eq!(response, [0xAA, 0xBB, 0x90, 0x00]);
+        assert_eq!(commands[1], [0x00, 0xB0, 0x00, 0x00, 0x02]);
+    }
+
+    #[test]
+    fn repeated_wrong_length_is_sent_again_only_once() {
+        let (response, commands) = chain_with_fake_card(
+            Protocols::T1,
+            &[0x00, 0xB0, 0x00, 0x00, 0x00],
+            &[&[0x6C, 0x02], &[0x6C, 0x01]],
+        );
+
+        assert_eq!(response, [0x6C, 0x01]);
+        assert_eq!(commands.len(), 2);
+    }
+
+    #[test]
+    fn wrong_length_then_t0_chain() {
+        let (response, commands) = chain_with_fake_card(
+            Protocols::T0,
+            &[0x00, 0xB0, 0x00, 0x00, 0x00],
+            &[
+                &[0x6C, 0x03],
+                &[0xAA, 0x61, 0x02],
+                &[0xBB, 0xCC, 0x90, 0x00],
+            ],
+        );
+
+        assert_eq!(response, [0xAA, 0xBB, 0xCC, 0x90, 0x00]);
+        assert_eq!(commands[1], [0x00, 0xB0, 0x00, 0x00, 0x03]);
+        assert_eq!(commands[2], [0x00, 0xC0, 0x00, 0x00, 0x02]);
+    }
+
+    #[test]
+    fn le_is_appended_without_le() {
+        // Case 1: header only
+        assert_eq!(
+            with_le(&[0x00, 0x20, 0x00, 0x00], 0x10),
+            [0x00, 0x20, 0x00, 0x00, 0x10]
+        );
+        // Case 3: header, Lc and data
+        assert_eq!(
+            with_le(&[0x00, 0xA4, 0x02, 0x0C, 0x02, 0x3F, 0x00], 0x10),
+            [0x00, 0xA4, 0x02, 0x0C, 0x02, 0x3F, 0x00, 0x10]
+        );
+    }
+
+    #[test]
+    fn le_is_replaced_with_le() {
+        // Case 2: header and Le
+        assert_eq!(
+            with_le(&[0x00, 0xB0, 0x00, 0x00, 0x00], 0x10),
+            [0x00, 0xB0, 0x00, 0x00, 0x10]
+        );
+        // Case 4: header, Lc, data and Le
+        assert_eq!(
+            with_le(&[0x00, 0xA4, 0x02, 0x00, 0x02, 0x3F, 0x00, 0x00], 0x10),
+            [0x00, 0xA4, 0x02, 0x00, 0x02, 0x3F, 0x00, 0x10]
+        );
+    }
+
+    #[test]
+    fn extended_le_is_two_bytes() {
+        // Case 2E
+        assert_eq!(
+            with_le(&[0x00, 0xB0, 0x00, 0x00, 0x00, 0x01, 0x00], 0x10),
+            [0x00, 0xB0, 0x00, 0x00, 0x00, 0x00, 0x10]
+        );
+        // Case 3E
+        assert_eq!(
+            with_le(&[0x00, 0xD6, 0x00, 0x00, 0x00, 0x00, 0x01, 0xAA], 0x10),
+            [0x00, 0xD6, 0x00, 0x00, 0x00, 0x00, 0x01, 0xAA, 0x00, 0x10]
+        );
+        // Case 4E
+        assert_eq!(
+            with_le(
+                &[0x00, 0xA4, 0x02, 0x00, 0x00, 0x00, 0x02, 0x3F, 0x00, 0x00, 0x00],
+                0x10
+            ),
+            [0x00, 0xA4, 0x02, 0x00, 0x00, 0x00, 0x02, 0x3F, 0x00, 0x00, 0x10]
+        );
+    }
+
+    #[tokio::test]
+    async fn reconnected_card_is_not_recreated() {
+        let result = reconnect_or_recreate("Test Reader", Ok::<(), String>(()), || async {
+            panic!("The card must not be recreated");
+            #[allow(unreachable_code)]
+            Ok::<(), BoxError>(())
+        })
+        .await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn recreated_card_is_usable() {
+        let result =
+            reconnect_or_recreate("Test Reader", Err("Reset failed".to_string()), || async {
+                Ok::<(), BoxError>(())
+            })
+            .await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn card_is_not_usable_when_both_fail() {
+        let result =
+            reconnect_or_recreate("Test Reader", Err("Reset failed".to_string()), || async {
+                Err::<(), BoxError>("No smart card inserted".into())
+            })
+            .await;
+
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("Reset failed"));
+        assert!(error.contains("No smart card inserted"));
+    }
+
+    #[test]
+    fn apdu_hex_is_normalized() {
+        assert_eq!(
+            normalize_apdu_hex("00a4020c023f00").unwrap(),
+            "00A4020C023F00"
+        );
+        assert_eq!(normalize_apdu_hex(" 0x00 A4\t02 0c\n").unwrap(), "00A4020C");
+        assert_eq!(normalize_apdu_hex("0X00B0").unwrap(), "00B0");
+        assert_eq!(normalize_apdu_hex("").unwrap(), "");
+    }
+
+    #[test]
+    fn odd_apdu_hex_is_rejected() {
+        let error = normalize_apdu_hex("00A40").unwrap_err();
+        assert!(error.contains("Odd number of hex digits (5)"), "{}", error);
+    }
+
+    #[test]
+    fn invalid_apdu_hex_character_is_rejected_with_its_position() {
+        let error = normalize_apdu_hex("00A4 0G").unwrap_err();
+        assert_eq!(error, "Invalid hex character 'G' at position 6");
+
+        // The position is counted in the original input, prefix included
+        let error = normalize_apdu_hex(" 0x00Z0").unwrap_err();
+        assert_eq!(error, "Invalid hex character 'Z' at position 5");
+    }
+
+    #[test]
+    fn prefix_is_only_accepted_at_the_start() {
+        assert!(normalize_apdu_hex("000x00").is_err());
+    }
+
+    #[tokio::test]
+    async fn non_utf8_reader_name_does_not_stop_the_monitor() {
+        // Latin-1 "Lecteur é" followed by an invalid byte, as a localized Windows may report it
+        let name = CString::new(b"Lecteur \xE9\xFF".to_vec()).unwrap();
+        assert!(name.to_str().is_err());
+
+        let mut reader_states = vec![ReaderState::new(name, PcscState::UNAWARE)];
+        assert!(process_reader_states(&mut reader_states).await.is_ok());
+    }
+
+    /// Reader wait answering with the given changes in order, then waiting without a change.
+    fn fake_reader_wait(
+        changes: Vec<Vec<&str>>,
+    ) -> impl FnMut(Duration) -> Result<Vec<String>, pcsc::Error> {
+        let mut changes: VecDeque<Vec<String>> = changes
+            .into_iter()
+            .map(|names| names.into_iter().map(String::from).collect())
+            .collect();
+
+        move |timeout| match changes.pop_front() {
+            Some(names) => Ok(names),
+            None => {
+                std::thread::sleep(timeout);
+                Err(pcsc::Error::Timeout)
+            }
+        }
+    }
+
+    /// Drives an insert, remove and insert of the card in one reader through the monitor steps:
+    /// the changes are settled, then the settled state is decided as in `process_reader_states`.
+    /// A created card is added to the pool in place of its connection. Returns the creates.
+    async fn creates_during_bounce(reader: &str, interval: Duration) -> u32 {
+        let atr = "3B9F96801FC78031E073FE211B63";
+        let mut states: VecDeque<&str> = VecDeque::from([atr, "", atr]);
+        let mut debounce = ReaderDebounce::default();
+        let mut creates = 0;
+
+        while let Some(mut settled) = states.pop_front() {
+            debounce
+                .settle(vec![reader.to_string()], interval, |timeout| {
+                    match states.pop_front() {
+                        Some(state) => {
+                            settled = state;
+                            Ok(vec![reader.to_string()])
+                        }
+                        None => {
+                            std::thread::sleep(timeout);
+                            Err(pcsc::Error::Timeout)
+                        }
+                    }
+                })
+                .unwrap();
+
+            if should_register_new_card(reader, settled).await == CardProcessingResult::Create {
+                creates += 1;
+                let (mqtt_client, _eventloop) =
+                    AsyncClient::new(rumqttc::v5::MqttOptions::new(reader, "localhost", 1883), 10);
+                TASK_POOL.lock().await.push(ProcessingCard {
+                    client_id: reader.to_string(),
+                    reader_name: Some(reader.to_string()),
+                    atr: Some(settled.to_string()),
+                    mqtt_client,
+                    task_handle: tauri::async_runtime::spawn(async {}),
+                    managed_card: None,
+                    online: Default::default(),
+                    metrics: Default::default(),
+                    auth_process: Default::default(),
+                    stop: Default::default(),
+                    paused: Default::default(),
+                    release_exchange: Default::default(),
+                });
+            }
+        }
+
+        TASK_POOL
+            .lock()
+            .await
+            .retain(|card| card.reader_name.as_deref() != Some(reader));
+        creates
+    }
+
+    #[tokio::test]
+    async fn bounce_is_coalesced_into_a_single_create() {
+        assert_eq!(
+            creates_during_bounce("Bounce Reader", Duration::from_millis(20)).await,
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn bounce_without_debounce_creates_the_card_twice() {
+        assert_eq!(
+            creates_during_bounce("Undebounced Reader", Duration::ZERO).await,
+            2
+        );
+    }
+
+    #[test]
+    fn change_of_another_reader_is_settled_too() {
+        let mut debounce = ReaderDebounce::default();
+
+        let started = Instant::now();
+        let bounces = debounce
+            .settle(
+                vec!["Reader A".to_string()],
+                Duration::from_millis(20),
+                fake_reader_wait(vec![vec!["Reader B"]]),
+            )
+            .unwrap();
+
+        assert_eq!(bounces, 1);
+        assert!(debounce.last_changes.contains_key("Reader B"));
+        assert!(started.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn zero_interval_does_not_wait() {
+        let mut debounce = ReaderDebounce::default();
+        let bounces = debounce
+            .settle(vec!["Reader A".to_string()], Duration::ZERO, |_| {
+                panic!("The readers must not be waited for")
+            })
+            .unwrap();
+
+        assert_eq!(bounces, 0);
+        assert!(debounce.last_changes.contains_key("Reader A"));
+    }
+
+    #[test]
+    fn transmit_error_is_returned() {
+        let result = chain_responses(Protocols::T0, &[0x00, 0xB0, 0x00, 0x00, 0x00], |_| {
+            Err::<Vec<u8>, _>("Card removed")
+        });
+
+        assert_eq!(result, Err("Card removed"));
+    }
 }